@@ -47,7 +47,7 @@ fn spawn(mut rt: Runtime) {
     rt.spawn(main_thought(30, 6));
     rt.spawn(main_thought(20, 5));
     rt.spawn(main_thought(40, 3));
-    j.join();
+    j.join().unwrap();
 }
 
 fn main() {
@@ -0,0 +1,250 @@
+use std::fs::File as StdFile;
+use std::future::Future;
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use crate::runtime::blocking::{spawn_blocking, BlockingTask};
+
+#[cfg(feature = "io-uring")]
+use crate::runtime::context::Handle;
+#[cfg(feature = "io-uring")]
+use crate::runtime::reactor::reactor::Reactor;
+#[cfg(feature = "io-uring")]
+use crate::runtime::reactor::uring::{self, Op};
+#[cfg(feature = "io-uring")]
+use alloc::sync::Arc;
+
+/// A file whose reads and writes are completed by the io_uring of the runtime it was opened in.
+///
+/// Regular files are always ready for epoll, so without a io_uring driver the operations run on
+/// the blocking pool instead
+pub struct File {
+    file: StdFile,
+    #[cfg(feature = "io-uring")]
+    reactor: Option<Arc<Reactor>>,
+}
+
+impl File {
+    /// opens a file in read-only mode, see [std::fs::File::open]
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        return Ok(Self::from_std(
+            spawn_blocking(move || StdFile::open(path)).await?,
+        ));
+    }
+
+    /// opens a file in write-only mode and truncates it, see [std::fs::File::create]
+    pub async fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        return Ok(Self::from_std(
+            spawn_blocking(move || StdFile::create(path)).await?,
+        ));
+    }
+
+    pub fn from_std(file: StdFile) -> Self {
+        return Self {
+            file,
+            #[cfg(feature = "io-uring")]
+            reactor: Handle::try_current()
+                .map(|handle| handle.reactor().clone())
+                .filter(|reactor| reactor.uring().is_some()),
+        };
+    }
+
+    pub fn into_std(self) -> StdFile {
+        return self.file;
+    }
+
+    /// reads into `buf` starting at `offset` of the file, returns how many bytes were read
+    pub fn read_at<'a>(&'a self, buf: &'a mut [u8], offset: u64) -> ReadAt<'a> {
+        return ReadAt {
+            file: self,
+            buf,
+            offset,
+            blocking: None,
+            #[cfg(feature = "io-uring")]
+            op: None,
+        };
+    }
+
+    /// writes `buf` starting at `offset` of the file, returns how many bytes were written
+    pub fn write_at<'a>(&'a self, buf: &'a [u8], offset: u64) -> WriteAt<'a> {
+        return WriteAt {
+            file: self,
+            buf,
+            offset,
+            blocking: None,
+            #[cfg(feature = "io-uring")]
+            op: None,
+        };
+    }
+}
+
+pub struct ReadAt<'a> {
+    file: &'a File,
+    buf: &'a mut [u8],
+    offset: u64,
+    blocking: Option<BlockingTask<(io::Result<usize>, Vec<u8>)>>,
+    #[cfg(feature = "io-uring")]
+    op: Option<Op<Vec<u8>>>,
+}
+
+impl Future for ReadAt<'_> {
+    type Output = io::Result<usize>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        #[cfg(feature = "io-uring")]
+        if let Some(reactor) = &this.file.reactor {
+            let op = match &mut this.op {
+                Some(op) => op,
+                None => this.op.insert(uring::read_at(
+                    reactor,
+                    &this.file.file,
+                    this.buf.len(),
+                    this.offset,
+                )?),
+            };
+
+            let res = ready!(Pin::new(op).poll(cx));
+            this.op = None;
+            let (n, data) = res?;
+            this.buf[..n].copy_from_slice(&data[..n]);
+            return Poll::Ready(Ok(n));
+        }
+
+        let task = match &mut this.blocking {
+            Some(task) => task,
+            None => {
+                // the read might outlive the future, so it works on its own descriptor
+                let file = this.file.file.try_clone()?;
+                let mut buf = vec![0; this.buf.len()];
+                let offset = this.offset;
+                this.blocking.insert(spawn_blocking(move || {
+                    (file.read_at(&mut buf, offset), buf)
+                }))
+            }
+        };
+        let (res, data) = ready!(Pin::new(task).poll(cx));
+        this.blocking = None;
+        let n = res?;
+        this.buf[..n].copy_from_slice(&data[..n]);
+        return Poll::Ready(Ok(n));
+    }
+}
+
+pub struct WriteAt<'a> {
+    file: &'a File,
+    buf: &'a [u8],
+    offset: u64,
+    blocking: Option<BlockingTask<io::Result<usize>>>,
+    #[cfg(feature = "io-uring")]
+    op: Option<Op<Vec<u8>>>,
+}
+
+impl Future for WriteAt<'_> {
+    type Output = io::Result<usize>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        #[cfg(feature = "io-uring")]
+        if let Some(reactor) = &this.file.reactor {
+            let op = match &mut this.op {
+                Some(op) => op,
+                None => this.op.insert(uring::write_at(
+                    reactor,
+                    &this.file.file,
+                    this.buf.to_vec(),
+                    this.offset,
+                )?),
+            };
+
+            let res = ready!(Pin::new(op).poll(cx));
+            this.op = None;
+            return Poll::Ready(res.map(|(n, _)| n));
+        }
+
+        let task = match &mut this.blocking {
+            Some(task) => task,
+            None => {
+                let file = this.file.file.try_clone()?;
+                let buf = this.buf.to_vec();
+                let offset = this.offset;
+                this.blocking
+                    .insert(spawn_blocking(move || file.write_at(&buf, offset)))
+            }
+        };
+        let res = ready!(Pin::new(task).poll(cx));
+        this.blocking = None;
+        return Poll::Ready(res);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::File;
+    use crate::prelude::*;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn write_and_read_at() {
+        let path = temp_path("write_and_read_at");
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let file = File::create(&path).await.unwrap();
+            assert_eq!(file.write_at(b"hello world", 0).await.unwrap(), 11);
+            assert_eq!(file.write_at(b"oxic!", 6).await.unwrap(), 5);
+
+            let file = File::open(&path).await.unwrap();
+            let mut buf = [0u8; 32];
+            let n = file.read_at(&mut buf, 0).await.unwrap();
+            assert_eq!(&buf[..n], b"hello oxic!");
+            let n = file.read_at(&mut buf, 6).await.unwrap();
+            assert_eq!(&buf[..n], b"oxic!");
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "io-uring")]
+    #[test]
+    fn uring_write_and_read_at() {
+        use crate::runtime::{reactor::poller::Backend, runtime::RuntimeBuilder};
+        use std::fs::OpenOptions;
+
+        let path = temp_path("uring_write_and_read_at");
+        let mut rt = RuntimeBuilder::new().backend(Backend::IoUring).build();
+        rt.block_on(async {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)
+                .unwrap();
+            let file = File::from_std(file);
+            assert!(file.reactor.is_some());
+
+            assert_eq!(file.write_at(b"completed", 0).await.unwrap(), 9);
+            let mut buf = [0u8; 32];
+            let n = file.read_at(&mut buf, 0).await.unwrap();
+            assert_eq!(&buf[..n], b"completed");
+
+            // dropping a submitted read leaves its buffer to the driver until it completes
+            let mut buf = [0u8; 32];
+            let mut read = file.read_at(&mut buf, 0);
+            let _ = poll_once(&mut read);
+            drop(read);
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "io-uring")]
+    fn poll_once<F: core::future::Future + Unpin>(fut: &mut F) -> core::task::Poll<F::Output> {
+        let waker = core::task::Waker::noop();
+        let mut cx = core::task::Context::from_waker(waker);
+        return core::pin::Pin::new(fut).poll(&mut cx);
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    fmt,
+    future::Future,
+    io,
+    os::fd::{AsRawFd, RawFd},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use crate::runtime::reactor::{
+    interest::Interest, registration::Registration, scheduled_io::ReadyEvent,
+};
+
+#[cfg(feature = "io-uring")]
+use crate::runtime::reactor::reactor::Reactor;
+#[cfg(feature = "io-uring")]
+use alloc::sync::Arc;
+
+/// A fd registered with the reactor of the runtime it was created in, so tasks can wait for it
+/// to become readable or writable.
+///
+/// The fd has to be in nonblocking mode. It is registered edge-triggered: once a operation on it
+/// returns [WouldBlock](io::ErrorKind::WouldBlock) the readiness has to be cleared through the
+/// guard, otherwise waiting for it completes right away again. [AsyncFdReadyGuard::try_io] does
+/// that on its own
+///
+/// ```
+/// use oxic::{io::async_fd::AsyncFd, prelude::Runtime};
+/// use std::os::unix::net::UnixDatagram;
+///
+/// let mut rt = Runtime::new();
+/// rt.block_on(async {
+///     let (a, b) = UnixDatagram::pair().unwrap();
+///     b.set_nonblocking(true).unwrap();
+///     let b = AsyncFd::new(b).unwrap();
+///     a.send(b"hello").unwrap();
+///
+///     let mut buf = [0; 5];
+///     loop {
+///         let mut guard = b.readable().await.unwrap();
+///         if let Ok(res) = guard.try_io(|fd| fd.get_ref().recv(&mut buf)) {
+///             assert_eq!(res.unwrap(), 5);
+///             break;
+///         }
+///     }
+/// });
+/// ```
+///
+/// # Panics
+/// creating it outside of the context of a runtime panics, see
+/// [Handle::current](crate::runtime::context::Handle::current)
+pub struct AsyncFd<T: AsRawFd> {
+    // declared before the inner value so it is deregistered before the fd gets closed
+    registration: Registration,
+    inner: T,
+}
+
+impl<T: AsRawFd> AsyncFd<T> {
+    /// registers `inner` for both reading and writing
+    pub fn new(inner: T) -> io::Result<Self> {
+        return Self::with_interest(inner, Interest::ReadWrite);
+    }
+
+    pub fn with_interest(inner: T, interest: Interest) -> io::Result<Self> {
+        let registration = Registration::new(inner.as_raw_fd(), interest)?;
+        return Ok(Self {
+            registration,
+            inner,
+        });
+    }
+
+    pub fn get_ref(&self) -> &T {
+        return &self.inner;
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        return &mut self.inner;
+    }
+
+    /// deregisters the fd and returns the wrapped value
+    pub fn into_inner(self) -> T {
+        let AsyncFd {
+            registration,
+            inner,
+        } = self;
+        drop(registration);
+        return inner;
+    }
+
+    /// waits until the fd is readable
+    pub fn readable(&self) -> Readable<'_, T> {
+        return Readable { fd: self };
+    }
+
+    /// waits until the fd is writable
+    pub fn writable(&self) -> Writable<'_, T> {
+        return Writable { fd: self };
+    }
+
+    pub fn poll_read_ready(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        return self.poll_ready(cx, Interest::Read);
+    }
+
+    pub fn poll_write_ready(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        return self.poll_ready(cx, Interest::Write);
+    }
+
+    fn poll_ready(
+        &self,
+        cx: &mut Context<'_>,
+        interest: Interest,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        let event = ready!(self.registration.poll_ready(cx, interest))?;
+        return Poll::Ready(Ok(AsyncFdReadyGuard {
+            fd: self,
+            event: Some(event),
+        }));
+    }
+
+    /// runs the nonblocking operation `f` until it doesn't return
+    /// [WouldBlock](io::ErrorKind::WouldBlock) anymore, waiting for `interest` in between
+    pub(crate) fn poll_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        interest: Interest,
+        mut f: impl FnMut(&T) -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        return self.registration.poll_io(cx, interest, || f(&self.inner));
+    }
+
+    #[cfg(feature = "io-uring")]
+    pub(crate) fn reactor(&self) -> &Arc<Reactor> {
+        return self.registration.reactor();
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for AsyncFd<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.inner.as_raw_fd();
+    }
+}
+
+pub struct Readable<'a, T: AsRawFd> {
+    fd: &'a AsyncFd<T>,
+}
+
+impl<'a, T: AsRawFd> Future for Readable<'a, T> {
+    type Output = io::Result<AsyncFdReadyGuard<'a, T>>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        return self.fd.poll_read_ready(cx);
+    }
+}
+
+pub struct Writable<'a, T: AsRawFd> {
+    fd: &'a AsyncFd<T>,
+}
+
+impl<'a, T: AsRawFd> Future for Writable<'a, T> {
+    type Output = io::Result<AsyncFdReadyGuard<'a, T>>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        return self.fd.poll_write_ready(cx);
+    }
+}
+
+/// The readiness of a [AsyncFd] returned by [AsyncFd::readable] and [AsyncFd::writable]. The
+/// readiness stays set after the guard is dropped unless it got cleared
+pub struct AsyncFdReadyGuard<'a, T: AsRawFd> {
+    fd: &'a AsyncFd<T>,
+    event: Option<ReadyEvent>,
+}
+
+impl<'a, T: AsRawFd> AsyncFdReadyGuard<'a, T> {
+    pub fn get_ref(&self) -> &'a AsyncFd<T> {
+        return self.fd;
+    }
+
+    /// forgets the readiness, the next wait for it blocks until the reactor reports it again.
+    /// Should be called once a operation returned [WouldBlock](io::ErrorKind::WouldBlock)
+    pub fn clear_ready(&mut self) {
+        if let Some(event) = self.event.take() {
+            self.fd.registration.clear_readiness(event);
+        }
+    }
+
+    /// keeps the readiness, which is what dropping the guard does as well
+    pub fn retain_ready(&mut self) {}
+
+    /// runs `f` and clears the readiness if it returned [WouldBlock](io::ErrorKind::WouldBlock),
+    /// in which case [TryIoError] is returned and the caller should wait again
+    pub fn try_io<R>(
+        &mut self,
+        f: impl FnOnce(&'a AsyncFd<T>) -> io::Result<R>,
+    ) -> Result<io::Result<R>, TryIoError> {
+        return match f(self.fd) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.clear_ready();
+                Err(TryIoError(()))
+            }
+            res => Ok(res),
+        };
+    }
+}
+
+/// Returned by [AsyncFdReadyGuard::try_io] when the operation would have blocked
+#[derive(Debug)]
+pub struct TryIoError(());
+
+impl fmt::Display for TryIoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("the operation would block");
+    }
+}
+
+impl std::error::Error for TryIoError {}
+
+#[cfg(test)]
+mod test {
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use super::AsyncFd;
+    use crate::prelude::Runtime;
+    use crate::test_util::Join;
+
+    fn eventfd() -> OwnedFd {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        assert!(fd >= 0);
+        return unsafe { OwnedFd::from_raw_fd(fd) };
+    }
+
+    fn read_eventfd(fd: &OwnedFd) -> std::io::Result<u64> {
+        let mut value = 0u64;
+        let n = unsafe {
+            libc::read(
+                fd.as_raw_fd(),
+                &mut value as *mut u64 as *mut libc::c_void,
+                8,
+            )
+        };
+        if n < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        return Ok(value);
+    }
+
+    fn write_eventfd(fd: &OwnedFd, value: u64) {
+        let n = unsafe {
+            libc::write(
+                fd.as_raw_fd(),
+                &value as *const u64 as *const libc::c_void,
+                8,
+            )
+        };
+        assert_eq!(n, 8);
+    }
+
+    #[test]
+    fn wait_for_eventfd() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let fd = AsyncFd::new(eventfd()).unwrap();
+
+            // the optimistic readiness of the new registration gets cleared by the first read
+            let mut guard = fd.readable().await.unwrap();
+            assert!(guard.try_io(|fd| read_eventfd(fd.get_ref())).is_err());
+
+            // the reader is already waiting when the eventfd gets written
+            let read = async {
+                loop {
+                    let mut guard = fd.readable().await.unwrap();
+                    if let Ok(res) = guard.try_io(|fd| read_eventfd(fd.get_ref())) {
+                        return res.unwrap();
+                    }
+                }
+            };
+            let write = async { write_eventfd(fd.get_ref(), 3) };
+            let (value, ()) = Join::new(read, write).await;
+            assert_eq!(value, 3);
+        });
+    }
+
+    #[test]
+    fn into_inner_deregisters() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let fd = AsyncFd::new(eventfd()).unwrap();
+            fd.writable().await.unwrap().clear_ready();
+
+            // registering the fd again only works if it got removed from the reactor
+            let fd = AsyncFd::new(fd.into_inner()).unwrap();
+            fd.writable().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn try_io_error_boxes() {
+        let mut rt = Runtime::new();
+        let res: Result<(), Box<dyn std::error::Error>> = rt.block_on(async {
+            let fd = AsyncFd::new(eventfd()).unwrap();
+            let mut guard = fd.readable().await?;
+            guard.try_io(|fd| read_eventfd(fd.get_ref()))??;
+            return Ok(());
+        });
+        assert_eq!(res.unwrap_err().to_string(), "the operation would block");
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    future::Future,
+    io::{self, Read, Write},
+    mem,
+    os::fd::{AsRawFd, RawFd},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+
+use super::async_fd::AsyncFd;
+use crate::net::sys;
+use crate::runtime::blocking::{spawn_blocking, BlockingTask};
+use crate::runtime::reactor::interest::Interest;
+
+/// the most bytes a single operation on the blocking pool transfers
+const MAX_BUF: usize = 64 * 1024;
+
+/// whether epoll can report readiness for `fd`. Regular files are always ready and can't be
+/// registered, ttys and other character devices are often shared with the parent process which
+/// doesn't expect them to turn nonblocking, so only pipes and sockets qualify
+pub(crate) fn is_pollable(fd: RawFd) -> bool {
+    let mut stat: libc::stat = unsafe { mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut stat) } < 0 {
+        return false;
+    }
+    let kind = stat.st_mode & libc::S_IFMT;
+    return kind == libc::S_IFIFO || kind == libc::S_IFSOCK;
+}
+
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = sys::cvt(unsafe { libc::fcntl(fd, libc::F_GETFL) })?;
+    sys::cvt(unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) })?;
+    return Ok(());
+}
+
+enum State {
+    /// holds the bytes a read got beyond what the caller asked for
+    Idle {
+        buf: Vec<u8>,
+        pos: usize,
+    },
+    Reading(BlockingTask<(io::Result<usize>, Vec<u8>)>),
+    /// a write that was already reported as done, its error surfaces on the next operation
+    Writing(BlockingTask<io::Result<()>>),
+}
+
+/// Reads and writes of a fd without readiness support, performed on the blocking pool
+pub(crate) struct Blocking<T> {
+    fd: RawFd,
+    inner: Arc<Mutex<T>>,
+    state: State,
+}
+
+impl<T: AsRawFd + Send + 'static> Blocking<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        return Self {
+            fd: inner.as_raw_fd(),
+            inner: Arc::new(Mutex::new(inner)),
+            state: State::Idle {
+                buf: Vec::new(),
+                pos: 0,
+            },
+        };
+    }
+
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>
+    where
+        T: Read,
+    {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            match &mut self.state {
+                State::Idle { buf: data, pos } if *pos < data.len() => {
+                    let n = buf.len().min(data.len() - *pos);
+                    buf[..n].copy_from_slice(&data[*pos..*pos + n]);
+                    *pos += n;
+                    return Poll::Ready(Ok(n));
+                }
+                State::Idle { buf: data, .. } => {
+                    let mut data = mem::take(data);
+                    data.resize(buf.len().min(MAX_BUF), 0);
+                    let inner = self.inner.clone();
+                    self.state = State::Reading(spawn_blocking(move || {
+                        let res = inner.lock().unwrap().read(&mut data);
+                        (res, data)
+                    }));
+                }
+                State::Reading(task) => {
+                    let (res, mut data) = ready!(Pin::new(task).poll(cx));
+                    let n = *res.as_ref().unwrap_or(&0);
+                    data.truncate(n);
+                    self.state = State::Idle { buf: data, pos: 0 };
+                    if res.is_err() || n == 0 {
+                        return Poll::Ready(res);
+                    }
+                }
+                State::Writing(task) => {
+                    let res = ready!(Pin::new(task).poll(cx));
+                    self.state = State::Idle {
+                        buf: Vec::new(),
+                        pos: 0,
+                    };
+                    res?;
+                }
+            }
+        }
+    }
+
+    /// copies `buf` and reports it as written right away, the pool writes it in the background.
+    /// A failed write is reported by the next operation
+    pub(crate) fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>
+    where
+        T: Write,
+    {
+        loop {
+            match &mut self.state {
+                State::Idle { .. } => {
+                    let data = buf[..buf.len().min(MAX_BUF)].to_vec();
+                    let n = data.len();
+                    let inner = self.inner.clone();
+                    self.state = State::Writing(spawn_blocking(move || {
+                        inner.lock().unwrap().write_all(&data)
+                    }));
+                    return Poll::Ready(Ok(n));
+                }
+                State::Writing(task) => {
+                    let res = ready!(Pin::new(task).poll(cx));
+                    self.state = State::Idle {
+                        buf: Vec::new(),
+                        pos: 0,
+                    };
+                    res?;
+                }
+                State::Reading(task) => {
+                    // the bytes of a read of a dropped future are kept for the next read
+                    let (res, mut data) = ready!(Pin::new(task).poll(cx));
+                    data.truncate(*res.as_ref().unwrap_or(&0));
+                    self.state = State::Idle { buf: data, pos: 0 };
+                    res?;
+                }
+            }
+        }
+    }
+
+    /// waits for the write in the background to finish
+    pub(crate) fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let State::Writing(task) = &mut self.state else {
+            return Poll::Ready(Ok(()));
+        };
+        let res = ready!(Pin::new(task).poll(cx));
+        self.state = State::Idle {
+            buf: Vec::new(),
+            pos: 0,
+        };
+        return Poll::Ready(res);
+    }
+}
+
+/// A fd driven by the reactor if it supports readiness, otherwise by the blocking pool
+pub(crate) enum MaybeBlocking<T: AsRawFd> {
+    Evented(AsyncFd<T>),
+    Blocking(Blocking<T>),
+}
+
+impl<T: AsRawFd + Send + 'static> MaybeBlocking<T> {
+    /// registers `inner` with the reactor of the current runtime and makes it nonblocking if it
+    /// is pollable
+    pub(crate) fn new(inner: T) -> io::Result<Self> {
+        let fd = inner.as_raw_fd();
+        if !is_pollable(fd) {
+            return Ok(Self::Blocking(Blocking::new(inner)));
+        }
+
+        let io = AsyncFd::new(inner)?;
+        set_nonblocking(fd)?;
+        return Ok(Self::Evented(io));
+    }
+
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>
+    where
+        T: Read,
+        for<'a> &'a T: Read,
+    {
+        return match self {
+            Self::Evented(io) => io.poll_io(cx, Interest::Read, |mut inner| inner.read(buf)),
+            Self::Blocking(blocking) => blocking.poll_read(cx, buf),
+        };
+    }
+
+    pub(crate) fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>
+    where
+        T: Write,
+        for<'a> &'a T: Write,
+    {
+        return match self {
+            Self::Evented(io) => io.poll_io(cx, Interest::Write, |mut inner| inner.write(buf)),
+            Self::Blocking(blocking) => blocking.poll_write(cx, buf),
+        };
+    }
+
+    pub(crate) fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        return match self {
+            Self::Evented(_) => Poll::Ready(Ok(())),
+            Self::Blocking(blocking) => blocking.poll_flush(cx),
+        };
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for MaybeBlocking<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        return match self {
+            Self::Evented(io) => io.as_raw_fd(),
+            Self::Blocking(blocking) => blocking.fd,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+
+    use super::Blocking;
+    use crate::prelude::Runtime;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn write_copies_buf() {
+        let path = temp_path("blocking-write");
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut blocking = Blocking::new(File::create(&path).unwrap());
+            let mut buf = *b"aaaa";
+            let n = std::future::poll_fn(|cx| blocking.poll_write(cx, &buf)).await;
+            assert_eq!(n.unwrap(), 4);
+
+            // the same buffer with new contents is a new write
+            buf.copy_from_slice(b"bbbb");
+            let n = std::future::poll_fn(|cx| blocking.poll_write(cx, &buf)).await;
+            assert_eq!(n.unwrap(), 4);
+            std::future::poll_fn(|cx| blocking.poll_flush(cx))
+                .await
+                .unwrap();
+        });
+        assert_eq!(std::fs::read(&path).unwrap(), b"aaaabbbb");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn write_error_on_flush() {
+        let path = temp_path("blocking-write-error");
+        std::fs::write(&path, b"").unwrap();
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            // writing to a file opened read-only fails in the background
+            let mut blocking = Blocking::new(File::open(&path).unwrap());
+            let n = std::future::poll_fn(|cx| blocking.poll_write(cx, b"lost")).await;
+            assert_eq!(n.unwrap(), 4);
+            let res = std::future::poll_fn(|cx| blocking.poll_flush(cx)).await;
+            assert!(res.is_err());
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,3 +1,10 @@
-pub mod stream;
+pub mod async_fd;
+pub(crate) mod blocking;
+pub mod pipe;
 pub mod read;
+pub mod stdio;
+pub mod stream;
 pub mod write;
+
+pub use pipe::pipe;
+pub use stdio::{stderr, stdin, stdout};
@@ -0,0 +1,168 @@
+use std::{
+    fs::File,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::{blocking::MaybeBlocking, read::AsyncRead, write::AsyncWrite};
+use crate::net::sys;
+
+/// creates a pipe whose ends are registered with the reactor of the current runtime
+///
+/// # Panics
+/// panics when called outside of the context of a runtime, see
+/// [Handle::current](crate::runtime::context::Handle::current)
+pub fn pipe() -> io::Result<(Sender, Receiver)> {
+    let mut fds = [0; 2];
+    sys::cvt(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) })?;
+    let (receiver, sender) =
+        unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+    return Ok((
+        Sender::from_owned_fd(sender)?,
+        Receiver::from_owned_fd(receiver)?,
+    ));
+}
+
+/// The writing end of a pipe
+pub struct Sender {
+    io: MaybeBlocking<File>,
+}
+
+impl Sender {
+    /// wraps a writable fd. Pipes and sockets are registered with the reactor and made
+    /// nonblocking, writes to anything else are performed on a blocking thread pool
+    pub fn from_owned_fd(fd: OwnedFd) -> io::Result<Self> {
+        return Ok(Self {
+            io: MaybeBlocking::new(File::from(fd))?,
+        });
+    }
+}
+
+impl AsRawFd for Sender {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.io.as_raw_fd();
+    }
+}
+
+impl AsyncWrite for Sender {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.get_mut().io.poll_write(cx, buf);
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        return self.get_mut().io.poll_flush(cx);
+    }
+}
+
+/// The reading end of a pipe
+pub struct Receiver {
+    io: MaybeBlocking<File>,
+}
+
+impl Receiver {
+    /// wraps a readable fd, see [Sender::from_owned_fd]
+    pub fn from_owned_fd(fd: OwnedFd) -> io::Result<Self> {
+        return Ok(Self {
+            io: MaybeBlocking::new(File::from(fd))?,
+        });
+    }
+}
+
+impl AsRawFd for Receiver {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.io.as_raw_fd();
+    }
+}
+
+impl AsyncRead for Receiver {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.get_mut().io.poll_read(cx, buf);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use std::os::fd::OwnedFd;
+
+    use super::{pipe, Receiver, Sender};
+    use crate::io::{blocking::MaybeBlocking, read::AsyncReadExt, write::AsyncWriteExt};
+    use crate::prelude::{Handle, Runtime};
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn send_through_pipe() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (mut sender, mut receiver) = pipe().unwrap();
+            assert!(matches!(sender.io, MaybeBlocking::Evented(_)));
+
+            // more than the pipe buffer holds, so the writer has to wait for the reader
+            let data = (0..256 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+            let expected = data.clone();
+            let writer = Handle::current().spawn(async move {
+                let mut written = 0;
+                while written < data.len() {
+                    written += sender.write(&data[written..]).await.unwrap();
+                }
+            });
+
+            let mut received = Vec::new();
+            let mut buf = [0; 4096];
+            loop {
+                let n = receiver.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                received.extend_from_slice(&buf[..n]);
+            }
+            writer.await.unwrap();
+            assert_eq!(received, expected);
+        });
+    }
+
+    #[test]
+    fn regular_file_fallback() {
+        let path = temp_path("pipe");
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let file = std::fs::File::create(&path).unwrap();
+            let mut sender = Sender::from_owned_fd(OwnedFd::from(file)).unwrap();
+            assert!(matches!(sender.io, MaybeBlocking::Blocking(_)));
+            assert_eq!(sender.write(b"blocking").await.unwrap(), 8);
+            sender.flush().await.unwrap();
+            drop(sender);
+
+            let mut content = String::new();
+            std::fs::File::open(&path)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            assert_eq!(content, "blocking");
+
+            let file = std::fs::File::open(&path).unwrap();
+            let mut receiver = Receiver::from_owned_fd(OwnedFd::from(file)).unwrap();
+            let mut buf = [0; 3];
+            let mut read = Vec::new();
+            loop {
+                let n = receiver.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                read.extend_from_slice(&buf[..n]);
+            }
+            assert_eq!(read, b"blocking");
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+}
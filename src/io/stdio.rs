@@ -0,0 +1,273 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::fs::OpenOptionsExt,
+    },
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::{
+    blocking::{is_pollable, Blocking, MaybeBlocking},
+    read::AsyncRead,
+    write::AsyncWrite,
+};
+
+/// One of the standard streams
+enum StdioFd {
+    /// the fd of the process, it is never closed
+    Shared(RawFd),
+    /// a new file description of the same pipe, its flags are ours to change
+    Reopened(File),
+}
+
+impl AsRawFd for StdioFd {
+    fn as_raw_fd(&self) -> RawFd {
+        return match self {
+            Self::Shared(fd) => *fd,
+            Self::Reopened(file) => file.as_raw_fd(),
+        };
+    }
+}
+
+impl Read for &StdioFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = unsafe {
+            libc::read(
+                self.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        return Ok(n as usize);
+    }
+}
+
+impl Read for StdioFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        return (&*self).read(buf);
+    }
+}
+
+impl Write for &StdioFd {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = unsafe {
+            libc::write(
+                self.as_raw_fd(),
+                buf.as_ptr() as *const libc::c_void,
+                buf.len(),
+            )
+        };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        return Ok(n as usize);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+impl Write for StdioFd {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        return (&*self).write(buf);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+/// drives the standard stream `fd` through the reactor if it is a pipe, otherwise through the
+/// blocking pool. Its file description is shared with std and usually with the parent process,
+/// neither expects it to turn nonblocking, so the pipe is opened again through procfs to get a
+/// description of its own. Sockets can't be opened that way and stay on the blocking pool
+fn stdio(fd: RawFd, write: bool) -> MaybeBlocking<StdioFd> {
+    if is_pollable(fd) {
+        let reopened = OpenOptions::new()
+            .read(!write)
+            .write(write)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(format!("/proc/self/fd/{}", fd));
+        if let Ok(io) = reopened.and_then(|file| MaybeBlocking::new(StdioFd::Reopened(file))) {
+            return io;
+        }
+    }
+    return MaybeBlocking::Blocking(Blocking::new(StdioFd::Shared(fd)));
+}
+
+/// The standard input of the process, see [stdin]
+pub struct Stdin {
+    io: MaybeBlocking<StdioFd>,
+}
+
+/// The standard output of the process, see [stdout]
+pub struct Stdout {
+    io: MaybeBlocking<StdioFd>,
+}
+
+/// The standard error of the process, see [stderr]
+pub struct Stderr {
+    io: MaybeBlocking<StdioFd>,
+}
+
+/// returns a handle to the standard input, which is read through the reactor if it is a pipe and
+/// on a blocking thread pool otherwise. A read on the pool that got further than the caller asked
+/// for keeps the rest for the next read, so only one handle should be read from
+pub fn stdin() -> Stdin {
+    return Stdin {
+        io: stdio(libc::STDIN_FILENO, false),
+    };
+}
+
+/// returns a handle to the standard output, written through the reactor if it is a pipe and on a
+/// blocking thread pool otherwise. A write on the pool returns once its bytes were copied,
+/// [flush](crate::io::write::AsyncWriteExt::flush) waits until they reached the fd
+pub fn stdout() -> Stdout {
+    return Stdout {
+        io: stdio(libc::STDOUT_FILENO, true),
+    };
+}
+
+/// returns a handle to the standard error, see [stdout]
+pub fn stderr() -> Stderr {
+    return Stderr {
+        io: stdio(libc::STDERR_FILENO, true),
+    };
+}
+
+impl AsyncRead for Stdin {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.get_mut().io.poll_read(cx, buf);
+    }
+}
+
+impl AsyncWrite for Stdout {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.get_mut().io.poll_write(cx, buf);
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        return self.get_mut().io.poll_flush(cx);
+    }
+}
+
+impl AsyncWrite for Stderr {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.get_mut().io.poll_write(cx, buf);
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        return self.get_mut().io.poll_flush(cx);
+    }
+}
+
+impl AsRawFd for Stdin {
+    fn as_raw_fd(&self) -> RawFd {
+        return libc::STDIN_FILENO;
+    }
+}
+
+impl AsRawFd for Stdout {
+    fn as_raw_fd(&self) -> RawFd {
+        return libc::STDOUT_FILENO;
+    }
+}
+
+impl AsRawFd for Stderr {
+    fn as_raw_fd(&self) -> RawFd {
+        return libc::STDERR_FILENO;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use super::stdio;
+    use crate::io::blocking::MaybeBlocking;
+    use crate::prelude::{Handle, Runtime};
+
+    fn nonblocking(fd: &impl AsRawFd) -> bool {
+        let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) };
+        return flags & libc::O_NONBLOCK != 0;
+    }
+
+    fn pipe() -> (std::fs::File, std::fs::File) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) }, 0);
+        let (reader, writer) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        return (reader.into(), writer.into());
+    }
+
+    #[test]
+    fn keeps_blocking_mode() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (sender, mut receiver) = std::os::unix::net::UnixStream::pair().unwrap();
+            let mut io = stdio(sender.as_raw_fd(), true);
+            // a socket can't be opened again, so it has to go through the pool
+            assert!(matches!(io, MaybeBlocking::Blocking(_)));
+            let n = std::future::poll_fn(|cx| io.poll_write(cx, b"hello")).await;
+            assert_eq!(n.unwrap(), 5);
+            std::future::poll_fn(|cx| io.poll_flush(cx)).await.unwrap();
+
+            // the flags are shared with everyone else using the fd, they stay untouched
+            assert!(!nonblocking(&sender));
+
+            let mut buf = [0; 5];
+            receiver.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"hello");
+        });
+    }
+
+    #[test]
+    fn pipe_through_reactor() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (mut reader, writer) = pipe();
+            let mut io = stdio(writer.as_raw_fd(), true);
+            assert!(matches!(io, MaybeBlocking::Evented(_)));
+            let n = std::future::poll_fn(|cx| io.poll_write(cx, b"hello")).await;
+            assert_eq!(n.unwrap(), 5);
+            assert!(!nonblocking(&writer));
+
+            let mut buf = [0; 5];
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"hello");
+
+            let (reader, mut writer) = pipe();
+            let mut io = stdio(reader.as_raw_fd(), false);
+            assert!(matches!(io, MaybeBlocking::Evented(_)));
+            let read = Handle::current().spawn(async move {
+                let mut buf = [0; 5];
+                let n = std::future::poll_fn(|cx| io.poll_read(cx, &mut buf)).await;
+                return (n.unwrap(), buf);
+            });
+            writer.write_all(b"world").unwrap();
+            let (n, buf) = read.await.unwrap();
+            assert_eq!(&buf[..n], b"world");
+            assert!(!nonblocking(&reader));
+        });
+    }
+}
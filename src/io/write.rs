@@ -12,6 +12,12 @@ pub trait AsyncWrite {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>>;
+
+    /// waits until everything written so far reached its destination, writers without a buffer
+    /// of their own are done right away
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        return Poll::Ready(Ok(()));
+    }
 }
 
 pub trait AsyncWriteExt {
@@ -21,6 +27,13 @@ pub trait AsyncWriteExt {
     {
         return Write { writer: self, buf };
     }
+
+    fn flush(&mut self) -> Flush<'_, Self>
+    where
+        Self: Sized,
+    {
+        return Flush { writer: self };
+    }
 }
 
 impl<T> AsyncWriteExt for T where T: AsyncWrite {}
@@ -43,6 +56,21 @@ where
     }
 }
 
+pub struct Flush<'a, T: ?Sized> {
+    writer: &'a mut T,
+}
+
+impl<T> Future for Flush<'_, T>
+where
+    T: AsyncWrite + Unpin + ?Sized,
+{
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        return Pin::new(&mut *self.writer).poll_flush(cx);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::{
@@ -4,36 +4,49 @@
 //! ```
 //! use oxic::prelude::*;
 //!
-//! fn main() {
-//! 	let mut rt = Runtime::new();
-//! 	rt.block_on(async_hello());
-//! }
-//!
 //! async fn async_hello() {
-//! 	println!("Hello, from async");
+//!     println!("Hello, from async");
 //! }
+//!
+//! let mut rt = Runtime::new();
+//! rt.block_on(async_hello());
 //! ```
 //! you can also use the oxic::main attribute to ach
 //!```
 //! #[oxic::main]
 //! async fn main() {
 //!
-//! 	println!("Hello, from async");
+//!     println!("Hello, from async");
 //! }
 //! ```
 extern crate alloc;
 
+// the modules spell out every `return`, that's the style of the crate
+#[allow(clippy::needless_return)]
 pub mod runtime;
 
+#[allow(clippy::needless_return)]
 pub mod fs;
+#[allow(clippy::needless_return)]
 pub mod io;
+#[allow(clippy::needless_return)]
 pub mod net;
+#[allow(clippy::needless_return)]
+pub mod process;
+#[allow(clippy::needless_return)]
+pub mod signal;
+
+#[cfg(test)]
+#[allow(clippy::needless_return)]
+mod test_util;
 
 pub use oxic_macros::main;
 pub mod prelude {
-    pub use crate::runtime::runtime::Runtime;
+    pub use crate::runtime::{context::Handle, runtime::Runtime};
     pub use crate::{
-        runtime::executor::executor::Executor, runtime::handle::JoinHandle,
+        runtime::executor::executor::Executor,
+        runtime::handle::{AbortHandle, JoinError, JoinHandle},
+        runtime::task::local::{spawn_local, LocalSet},
         runtime::task::task::Task,
     };
 }
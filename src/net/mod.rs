@@ -1,2 +1,4 @@
+pub(crate) mod sys;
 pub mod tcp;
 pub mod udp;
+pub mod unix;
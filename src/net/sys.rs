@@ -0,0 +1,174 @@
+//! thin wrappers around the socket syscalls std doesn't expose
+
+use std::io;
+use std::mem;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// turns the `-1` of a failed syscall into the error it set
+pub(crate) fn cvt(res: libc::c_int) -> io::Result<libc::c_int> {
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    return Ok(res);
+}
+
+/// creates a nonblocking, close-on-exec socket
+pub(crate) fn socket(domain: libc::c_int, ty: libc::c_int) -> io::Result<OwnedFd> {
+    let fd =
+        cvt(unsafe { libc::socket(domain, ty | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, 0) })?;
+    return Ok(unsafe { OwnedFd::from_raw_fd(fd) });
+}
+
+pub(crate) fn setsockopt<T>(
+    fd: &impl AsRawFd,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: T,
+) -> io::Result<()> {
+    cvt(unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            level,
+            name,
+            &value as *const T as *const libc::c_void,
+            mem::size_of::<T>() as libc::socklen_t,
+        )
+    })?;
+    return Ok(());
+}
+
+pub(crate) fn getsockopt<T: Copy>(
+    fd: &impl AsRawFd,
+    level: libc::c_int,
+    name: libc::c_int,
+) -> io::Result<T> {
+    let mut value: T = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<T>() as libc::socklen_t;
+    cvt(unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            level,
+            name,
+            &mut value as *mut T as *mut libc::c_void,
+            &mut len,
+        )
+    })?;
+    return Ok(value);
+}
+
+/// converts `addr` into the representation the socket syscalls expect
+pub(crate) fn sockaddr(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sin = libc::sockaddr_in {
+                sin_family: libc::AF_INET as libc::sa_family_t,
+                sin_port: addr.port().to_be(),
+                sin_addr: libc::in_addr {
+                    s_addr: u32::from_ne_bytes(addr.ip().octets()),
+                },
+                sin_zero: [0; 8],
+            };
+            unsafe {
+                (&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in).write(sin)
+            };
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = libc::sockaddr_in6 {
+                sin6_family: libc::AF_INET6 as libc::sa_family_t,
+                sin6_port: addr.port().to_be(),
+                sin6_flowinfo: addr.flowinfo(),
+                sin6_addr: libc::in6_addr {
+                    s6_addr: addr.ip().octets(),
+                },
+                sin6_scope_id: addr.scope_id(),
+            };
+            unsafe {
+                (&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in6).write(sin6)
+            };
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    return (storage, len as libc::socklen_t);
+}
+
+/// the inverse of [sockaddr]
+pub(crate) fn socket_addr(storage: &libc::sockaddr_storage) -> io::Result<SocketAddr> {
+    return match storage.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let sin = unsafe { *(storage as *const _ as *const libc::sockaddr_in) };
+            Ok(SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::from(sin.sin_addr.s_addr.to_ne_bytes()),
+                u16::from_be(sin.sin_port),
+            )))
+        }
+        libc::AF_INET6 => {
+            let sin6 = unsafe { *(storage as *const _ as *const libc::sockaddr_in6) };
+            Ok(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(sin6.sin6_addr.s6_addr),
+                u16::from_be(sin6.sin6_port),
+                sin6.sin6_flowinfo,
+                sin6.sin6_scope_id,
+            )))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid socket address family",
+        )),
+    };
+}
+
+/// the address `fd` is bound to
+pub(crate) fn local_addr(fd: &impl AsRawFd) -> io::Result<SocketAddr> {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    cvt(unsafe {
+        libc::getsockname(
+            fd.as_raw_fd(),
+            &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+            &mut len,
+        )
+    })?;
+    return socket_addr(&storage);
+}
+
+/// converts the unix socket address `addr` into a `sockaddr_un`, unnamed addresses can't be
+/// connected or bound to
+pub(crate) fn unix_sockaddr(
+    addr: &std::os::unix::net::SocketAddr,
+) -> io::Result<(libc::sockaddr_un, libc::socklen_t)> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut sun: libc::sockaddr_un = unsafe { mem::zeroed() };
+    sun.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    // the path is nul terminated, an abstract name starts with a nul byte instead
+    let (offset, name) = match (addr.as_pathname(), addr.as_abstract_name()) {
+        (Some(path), _) => (0, path.as_os_str().as_bytes()),
+        (None, Some(name)) => (1, name),
+        (None, None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "can't connect to a unnamed unix socket address",
+            ))
+        }
+    };
+    if offset + name.len() >= sun.sun_path.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "unix socket address too long",
+        ));
+    }
+    for (dst, src) in sun.sun_path[offset..].iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+
+    let path_offset = mem::offset_of!(libc::sockaddr_un, sun_path);
+    let len = match offset {
+        0 => path_offset + name.len() + 1,
+        _ => path_offset + 1 + name.len(),
+    };
+    return Ok((sun, len as libc::socklen_t));
+}
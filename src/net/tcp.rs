@@ -1,67 +1,749 @@
-use std::future::Future;
-use std::io;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::io::{self, Read, Write};
 use std::net::SocketAddr;
 use std::net::TcpListener as StdTcpListener;
 use std::net::TcpStream as StdTcpStream;
-use std::net::ToSocketAddrs;
-use std::os::fd::AsRawFd;
+use std::net::{Shutdown, ToSocketAddrs};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
 use std::pin::Pin;
 use std::sync::Arc;
-use std::task::Context;
-use std::task::Poll;
+use std::task::{ready, Context, Poll};
 
+use super::sys;
+use crate::io::{async_fd::AsyncFd, read::AsyncRead, stream::Stream, write::AsyncWrite};
 use crate::runtime::reactor::interest::Interest;
-use crate::runtime::reactor::reactor::Reactor;
 
+/// A TCP connection registered with the reactor of the runtime it was created in
+///
+/// # Panics
+/// creating it outside of the context of a runtime panics, see
+/// [Handle::current](crate::runtime::context::Handle::current)
 pub struct TcpStream {
-    stream: Arc<StdTcpStream>,
+    io: AsyncFd<StdTcpStream>,
 }
 
 impl TcpStream {
-    pub fn from_std(stream: StdTcpStream) -> Self {
-        todo!();
+    /// opens a connection to `addr`, every resolved address is tried until one succeeds
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in addr.to_socket_addrs()? {
+            match Self::connect_addr(addr).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        return Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any addresses",
+            )
+        }));
+    }
+
+    async fn connect_addr(addr: SocketAddr) -> io::Result<TcpStream> {
+        let socket = match addr {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        return socket.connect(addr).await;
+    }
+
+    /// resolves once a non-blocking connect finished, writability alone doesn't tell whether it
+    /// succeeded so the pending error of the socket is checked afterwards
+    fn poll_connected(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.io.poll_write_ready(cx))?;
+            if let Some(e) = self.io.get_ref().take_error()? {
+                return Poll::Ready(Err(e));
+            }
+
+            match self.io.get_ref().peer_addr() {
+                Ok(_) => return Poll::Ready(Ok(())),
+                // the readiness was the optimistic one of a new registration
+                Err(e) if e.raw_os_error() == Some(libc::ENOTCONN) => guard.clear_ready(),
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+
+    pub fn from_std(stream: StdTcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        return Ok(Self {
+            io: AsyncFd::new(stream)?,
+        });
+    }
+
+    /// deregisters the stream from the reactor and returns the underlying std stream, which is
+    /// still in nonblocking mode
+    pub fn into_std(self) -> StdTcpStream {
+        return self.io.into_inner();
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().peer_addr();
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().local_addr();
+    }
+
+    /// shuts down the read half, the write half or both halves of the connection
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        return self.io.get_ref().shutdown(how);
+    }
+
+    pub fn nodelay(&self) -> io::Result<bool> {
+        return self.io.get_ref().nodelay();
+    }
+
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        return self.io.get_ref().set_nodelay(nodelay);
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        return self.io.get_ref().ttl();
+    }
+
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        return self.io.get_ref().set_ttl(ttl);
+    }
+}
+
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.io.get_ref().as_raw_fd();
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.get_mut().poll_read_shared(cx, buf);
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.get_mut().poll_write_shared(cx, buf);
+    }
+}
+
+impl TcpStream {
+    /// splits the stream into a reading and a writing half that can be used from two tasks at
+    /// once, the reactor wakes the reader and the writer independently
+    pub fn split(&mut self) -> (ReadHalf<'_>, WriteHalf<'_>) {
+        return (ReadHalf { stream: self }, WriteHalf { stream: self });
+    }
+
+    /// like [TcpStream::split], but the halves own the stream so they can be moved into
+    /// spawned tasks. [OwnedReadHalf::reunite] puts them back together
+    pub fn into_split(self) -> (OwnedReadHalf, OwnedWriteHalf) {
+        let stream = Arc::new(self);
+        return (
+            OwnedReadHalf {
+                stream: stream.clone(),
+            },
+            OwnedWriteHalf { stream },
+        );
+    }
+
+    // reads and writes only need a shared reference, the reactor keeps a waker per direction
+    fn poll_read_shared(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        return self
+            .io
+            .poll_io(cx, Interest::Read, |mut stream| stream.read(buf));
+    }
+
+    fn poll_write_shared(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        return self
+            .io
+            .poll_io(cx, Interest::Write, |mut stream| stream.write(buf));
+    }
+}
+
+/// The reading half of a [TcpStream] borrowed through [TcpStream::split]
+pub struct ReadHalf<'a> {
+    stream: &'a TcpStream,
+}
+
+impl ReadHalf<'_> {
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.peer_addr();
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.local_addr();
+    }
+}
+
+impl AsyncRead for ReadHalf<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.stream.poll_read_shared(cx, buf);
+    }
+}
+
+/// The writing half of a [TcpStream] borrowed through [TcpStream::split]
+pub struct WriteHalf<'a> {
+    stream: &'a TcpStream,
+}
+
+impl WriteHalf<'_> {
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.peer_addr();
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.local_addr();
+    }
+
+    /// shuts down the writing direction, the peer reads the end of the stream
+    pub fn shutdown(&self) -> io::Result<()> {
+        return self.stream.shutdown(Shutdown::Write);
+    }
+}
+
+impl AsyncWrite for WriteHalf<'_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.stream.poll_write_shared(cx, buf);
     }
 }
 
+/// The reading half of a [TcpStream] created by [TcpStream::into_split]
+pub struct OwnedReadHalf {
+    stream: Arc<TcpStream>,
+}
+
+impl OwnedReadHalf {
+    /// puts the halves back together, fails if they don't belong to the same stream
+    pub fn reunite(self, other: OwnedWriteHalf) -> Result<TcpStream, ReuniteError> {
+        if !Arc::ptr_eq(&self.stream, &other.stream) {
+            return Err(ReuniteError(self, other));
+        }
+
+        drop(other);
+        return Ok(Arc::try_unwrap(self.stream)
+            .ok()
+            .expect("the stream is only shared by its two halves"));
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.peer_addr();
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.local_addr();
+    }
+}
+
+impl AsyncRead for OwnedReadHalf {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.stream.poll_read_shared(cx, buf);
+    }
+}
+
+/// The writing half of a [TcpStream] created by [TcpStream::into_split]
+pub struct OwnedWriteHalf {
+    stream: Arc<TcpStream>,
+}
+
+impl OwnedWriteHalf {
+    /// see [OwnedReadHalf::reunite]
+    pub fn reunite(self, other: OwnedReadHalf) -> Result<TcpStream, ReuniteError> {
+        return other.reunite(self);
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.peer_addr();
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.stream.local_addr();
+    }
+
+    /// shuts down the writing direction, the peer reads the end of the stream
+    pub fn shutdown(&self) -> io::Result<()> {
+        return self.stream.shutdown(Shutdown::Write);
+    }
+}
+
+impl AsyncWrite for OwnedWriteHalf {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        return self.stream.poll_write_shared(cx, buf);
+    }
+}
+
+/// Returned by [OwnedReadHalf::reunite] when the halves belong to different streams, hands both
+/// of them back
+pub struct ReuniteError(pub OwnedReadHalf, pub OwnedWriteHalf);
+
+impl fmt::Debug for ReuniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("ReuniteError(..)");
+    }
+}
+
+impl fmt::Display for ReuniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("tried to reunite halves that are not from the same stream");
+    }
+}
+
+impl std::error::Error for ReuniteError {}
+
 pub struct TcpListener {
-    listener: Arc<StdTcpListener>,
+    io: AsyncFd<StdTcpListener>,
 }
 
 impl TcpListener {
+    /// binds a listener with the default options, see [TcpSocket] for changing them first
     pub async fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<TcpListener> {
         let listener = StdTcpListener::bind(addr)?;
+        return Self::from_std(listener);
+    }
+
+    pub fn from_std(listener: StdTcpListener) -> io::Result<Self> {
         listener.set_nonblocking(true)?;
         return Ok(Self {
-            listener: Arc::new(listener),
+            io: AsyncFd::with_interest(listener, Interest::Read)?,
         });
     }
 
-    pub async fn accept(&self) -> Accept {
-        let accept = Accept {
-            listener: self.listener.clone(),
-        };
-        return accept;
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().local_addr();
+    }
+
+    /// waits for a new connection. Errors like running out of fds (`EMFILE`) or a connection
+    /// that got aborted before it was accepted are returned, the listener stays usable after them
+    pub fn accept(&self) -> Accept<'_> {
+        return Accept { listener: self };
+    }
+
+    /// the accepted connections as a [Stream], which never ends
+    pub fn incoming(&self) -> Incoming<'_> {
+        return Incoming { listener: self };
+    }
+
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        let (stream, addr) = ready!(self
+            .io
+            .poll_io(cx, Interest::Read, |listener| listener.accept()))?;
+        return Poll::Ready(Ok((TcpStream::from_std(stream)?, addr)));
     }
 }
 
-pub struct Accept {
-    listener: Arc<StdTcpListener>,
+pub struct Accept<'a> {
+    listener: &'a TcpListener,
 }
 
-impl Future for Accept {
+impl Future for Accept<'_> {
     type Output = io::Result<(TcpStream, SocketAddr)>;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.listener.accept() {
-            Ok(res) => Poll::Ready(Ok((TcpStream::from_std(res.0), res.1))),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                Reactor::get().register(
-                    self.listener.as_raw_fd(),
-                    cx.waker().clone(),
-                    Interest::ReadWrite,
-                );
-                return Poll::Pending;
+        return self.listener.poll_accept(cx);
+    }
+}
+
+pub struct Incoming<'a> {
+    listener: &'a TcpListener,
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<TcpStream>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = ready!(self.listener.poll_accept(cx));
+        return Poll::Ready(Some(res.map(|(stream, _)| stream)));
+    }
+}
+
+/// A socket that isn't a listener or connection yet, so options that have to be set before
+/// binding or connecting can be changed
+///
+/// ```
+/// use oxic::{net::tcp::TcpSocket, prelude::Runtime};
+///
+/// let mut rt = Runtime::new();
+/// rt.block_on(async {
+///     let socket = TcpSocket::new_v4().unwrap();
+///     socket.set_reuseport(true).unwrap();
+///     socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+///     let listener = socket.listen(1024).unwrap();
+/// });
+/// ```
+pub struct TcpSocket {
+    fd: OwnedFd,
+}
+
+impl TcpSocket {
+    pub fn new_v4() -> io::Result<Self> {
+        return Ok(Self {
+            fd: sys::socket(libc::AF_INET, libc::SOCK_STREAM)?,
+        });
+    }
+
+    pub fn new_v6() -> io::Result<Self> {
+        return Ok(Self {
+            fd: sys::socket(libc::AF_INET6, libc::SOCK_STREAM)?,
+        });
+    }
+
+    /// allows binding to a address that still has connections in `TIME_WAIT`
+    pub fn set_reuseaddr(&self, reuseaddr: bool) -> io::Result<()> {
+        return self.set_flag(libc::SOL_SOCKET, libc::SO_REUSEADDR, reuseaddr);
+    }
+
+    pub fn reuseaddr(&self) -> io::Result<bool> {
+        return self.flag(libc::SOL_SOCKET, libc::SO_REUSEADDR);
+    }
+
+    /// allows multiple sockets to bind the same address, the kernel balances incoming
+    /// connections between their listeners
+    pub fn set_reuseport(&self, reuseport: bool) -> io::Result<()> {
+        return self.set_flag(libc::SOL_SOCKET, libc::SO_REUSEPORT, reuseport);
+    }
+
+    pub fn reuseport(&self) -> io::Result<bool> {
+        return self.flag(libc::SOL_SOCKET, libc::SO_REUSEPORT);
+    }
+
+    pub fn set_send_buffer_size(&self, size: u32) -> io::Result<()> {
+        return sys::setsockopt(
+            &self.fd,
+            libc::SOL_SOCKET,
+            libc::SO_SNDBUF,
+            size as libc::c_int,
+        );
+    }
+
+    /// the size of the send buffer, linux reports double of what was set for its bookkeeping
+    pub fn send_buffer_size(&self) -> io::Result<u32> {
+        return sys::getsockopt::<libc::c_int>(&self.fd, libc::SOL_SOCKET, libc::SO_SNDBUF)
+            .map(|size| size as u32);
+    }
+
+    pub fn set_recv_buffer_size(&self, size: u32) -> io::Result<()> {
+        return sys::setsockopt(
+            &self.fd,
+            libc::SOL_SOCKET,
+            libc::SO_RCVBUF,
+            size as libc::c_int,
+        );
+    }
+
+    /// the size of the receive buffer, linux reports double of what was set for its bookkeeping
+    pub fn recv_buffer_size(&self) -> io::Result<u32> {
+        return sys::getsockopt::<libc::c_int>(&self.fd, libc::SOL_SOCKET, libc::SO_RCVBUF)
+            .map(|size| size as u32);
+    }
+
+    /// restricts a ipv6 socket to ipv6, otherwise it also handles ipv4 through mapped addresses
+    pub fn set_only_v6(&self, only_v6: bool) -> io::Result<()> {
+        return self.set_flag(libc::IPPROTO_IPV6, libc::IPV6_V6ONLY, only_v6);
+    }
+
+    pub fn only_v6(&self) -> io::Result<bool> {
+        return self.flag(libc::IPPROTO_IPV6, libc::IPV6_V6ONLY);
+    }
+
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        return self.set_flag(libc::IPPROTO_TCP, libc::TCP_NODELAY, nodelay);
+    }
+
+    pub fn nodelay(&self) -> io::Result<bool> {
+        return self.flag(libc::IPPROTO_TCP, libc::TCP_NODELAY);
+    }
+
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<()> {
+        let (storage, len) = sys::sockaddr(&addr);
+        sys::cvt(unsafe {
+            libc::bind(
+                self.fd.as_raw_fd(),
+                &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+                len,
+            )
+        })?;
+        return Ok(());
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return sys::local_addr(&self.fd);
+    }
+
+    /// turns the socket into a listener registered with the reactor of the current runtime,
+    /// `backlog` is the number of pending connections the kernel queues
+    pub fn listen(self, backlog: u32) -> io::Result<TcpListener> {
+        let backlog = backlog.min(libc::c_int::MAX as u32) as libc::c_int;
+        sys::cvt(unsafe { libc::listen(self.fd.as_raw_fd(), backlog) })?;
+        return TcpListener::from_std(StdTcpListener::from(self.fd));
+    }
+
+    /// connects to `addr` and returns the connection registered with the reactor of the
+    /// current runtime
+    pub async fn connect(self, addr: SocketAddr) -> io::Result<TcpStream> {
+        let (storage, len) = sys::sockaddr(&addr);
+        let res = unsafe {
+            libc::connect(
+                self.fd.as_raw_fd(),
+                &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+                len,
+            )
+        };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EINPROGRESS) {
+                return Err(err);
             }
-            Err(_) => todo!(),
         }
+
+        let stream = TcpStream::from_std(StdTcpStream::from(self.fd))?;
+        poll_fn(|cx| stream.poll_connected(cx)).await?;
+        return Ok(stream);
+    }
+
+    fn set_flag(&self, level: libc::c_int, name: libc::c_int, value: bool) -> io::Result<()> {
+        return sys::setsockopt(&self.fd, level, name, value as libc::c_int);
+    }
+
+    fn flag(&self, level: libc::c_int, name: libc::c_int) -> io::Result<bool> {
+        return sys::getsockopt::<libc::c_int>(&self.fd, level, name).map(|value| value != 0);
+    }
+}
+
+impl AsRawFd for TcpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.fd.as_raw_fd();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::{Shutdown, TcpListener as StdTcpListener};
+
+    use super::{TcpListener, TcpSocket, TcpStream};
+    use crate::io::{read::AsyncReadExt, stream::StreamExt, write::AsyncWriteExt};
+    use crate::prelude::Runtime;
+
+    #[test]
+    fn connect_and_echo() {
+        let listener = StdTcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 5];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+        });
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), addr);
+            stream.set_nodelay(true).unwrap();
+            assert!(stream.nodelay().unwrap());
+            stream.set_ttl(42).unwrap();
+            assert_eq!(stream.ttl().unwrap(), 42);
+
+            assert_eq!(stream.write("hello".as_bytes()).await.unwrap(), 5);
+            let mut buf = [0; 5];
+            let mut read = 0;
+            while read < 5 {
+                read += stream.read(&mut buf[read..]).await.unwrap();
+            }
+            assert_eq!(buf, "hello".as_bytes());
+        });
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn connect_refused() {
+        let addr = {
+            let listener = StdTcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        let mut rt = Runtime::new();
+        let err = rt
+            .block_on(TcpStream::connect(addr))
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    }
+
+    #[test]
+    fn accept_and_shutdown() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let client = std::thread::spawn(move || {
+                let mut stream = std::net::TcpStream::connect(addr).unwrap();
+                stream.write_all("hello".as_bytes()).unwrap();
+                stream.shutdown(Shutdown::Write).unwrap();
+                let mut buf = Vec::new();
+                stream.read_to_end(&mut buf).unwrap();
+                assert!(buf.is_empty());
+            });
+
+            let (mut stream, peer) = listener.accept().await.unwrap();
+            assert_eq!(stream.local_addr().unwrap(), addr);
+            assert_eq!(stream.peer_addr().unwrap(), peer);
+
+            let mut buf = [0; 16];
+            let mut read = 0;
+            loop {
+                let n = stream.read(&mut buf[read..]).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                read += n;
+            }
+            assert_eq!(&buf[..read], "hello".as_bytes());
+
+            stream.shutdown(Shutdown::Write).unwrap();
+            client.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn incoming_connections() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let clients = std::thread::spawn(move || {
+                for i in 0..3u8 {
+                    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+                    stream.write_all(&[i]).unwrap();
+                }
+            });
+
+            let mut incoming = listener.incoming();
+            for i in 0..3u8 {
+                let mut stream = incoming.next().await.unwrap().unwrap();
+                let mut buf = [0; 1];
+                assert_eq!(stream.read(&mut buf).await.unwrap(), 1);
+                assert_eq!(buf[0], i);
+            }
+            clients.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn socket_options() {
+        let socket = TcpSocket::new_v6().unwrap();
+        socket.set_reuseaddr(true).unwrap();
+        assert!(socket.reuseaddr().unwrap());
+        socket.set_reuseport(true).unwrap();
+        assert!(socket.reuseport().unwrap());
+        socket.set_only_v6(true).unwrap();
+        assert!(socket.only_v6().unwrap());
+        socket.set_nodelay(true).unwrap();
+        assert!(socket.nodelay().unwrap());
+        socket.set_send_buffer_size(64 * 1024).unwrap();
+        assert!(socket.send_buffer_size().unwrap() >= 64 * 1024);
+        socket.set_recv_buffer_size(64 * 1024).unwrap();
+        assert!(socket.recv_buffer_size().unwrap() >= 64 * 1024);
+    }
+
+    #[test]
+    fn reuseport_listeners() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let first = TcpSocket::new_v4().unwrap();
+            first.set_reuseport(true).unwrap();
+            first.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+            let addr = first.local_addr().unwrap();
+
+            let second = TcpSocket::new_v4().unwrap();
+            second.set_reuseport(true).unwrap();
+            second.bind(addr).unwrap();
+
+            let first = first.listen(16).unwrap();
+            let _second = second.listen(16).unwrap();
+            assert_eq!(first.local_addr().unwrap(), addr);
+
+            let socket = TcpSocket::new_v4().unwrap();
+            socket.set_nodelay(true).unwrap();
+            let stream = socket.connect(addr).await.unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), addr);
+            assert!(stream.nodelay().unwrap());
+        });
+    }
+
+    #[test]
+    fn split_read_and_write_concurrently() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let (server, _) = listener.accept().await.unwrap();
+
+            // the reader is parked on the connection while the writer sends on it
+            let (mut reader, mut writer) = server.into_split();
+            let read_task = crate::prelude::Handle::current().spawn(async move {
+                let mut buf = [0; 4];
+                let mut read = 0;
+                while read < 4 {
+                    read += reader.read(&mut buf[read..]).await.unwrap();
+                }
+                assert_eq!(&buf, b"ping");
+                reader
+            });
+            let write_task = crate::prelude::Handle::current().spawn(async move {
+                writer.write(b"pong").await.unwrap();
+                writer
+            });
+
+            let (mut client_reader, mut client_writer) = client.split();
+            let mut buf = [0; 4];
+            let mut read = 0;
+            while read < 4 {
+                read += client_reader.read(&mut buf[read..]).await.unwrap();
+            }
+            assert_eq!(&buf, b"pong");
+            client_writer.write(b"ping").await.unwrap();
+
+            let reader = read_task.await.unwrap();
+            let writer = write_task.await.unwrap();
+            let server = reader.reunite(writer).unwrap();
+            assert_eq!(server.local_addr().unwrap(), addr);
+        });
+    }
+
+    #[test]
+    fn reunite_mismatched_halves() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let (first_reader, _) = TcpStream::connect(addr).await.unwrap().into_split();
+            let (_, second_writer) = TcpStream::connect(addr).await.unwrap().into_split();
+            assert!(first_reader.reunite(second_writer).is_err());
+        });
     }
 }
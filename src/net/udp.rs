@@ -1,16 +1,23 @@
 use std::future::Future;
 use std::net::{SocketAddr, UdpSocket as StdUdpSocket};
-use std::os::fd::{AsRawFd, FromRawFd};
 use std::pin::Pin;
-use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::{io, net::ToSocketAddrs};
 
+use crate::io::async_fd::AsyncFd;
 use crate::runtime::reactor::interest::Interest;
-use crate::runtime::reactor::reactor::Reactor;
-
+#[cfg(feature = "io-uring")]
+use crate::runtime::reactor::uring::{self, Op};
+#[cfg(feature = "io-uring")]
+use std::task::ready;
+
+/// A UDP socket registered with the reactor of the runtime it was created in
+///
+/// # Panics
+/// creating it outside of the context of a runtime panics, see
+/// [Handle::current](crate::runtime::context::Handle::current)
 pub struct UdpSocket {
-    socket: Arc<StdUdpSocket>,
+    io: AsyncFd<StdUdpSocket>,
 }
 
 impl UdpSocket {
@@ -19,169 +26,188 @@ impl UdpSocket {
         A: ToSocketAddrs,
     {
         let sock = StdUdpSocket::bind(addr)?;
-        sock.set_nonblocking(true)?;
-
-        return Ok(UdpSocket {
-            socket: Arc::new(sock),
-        });
+        return Self::from_std(sock);
     }
 
-    pub fn from_std(sock: StdUdpSocket) -> Self {
-        return Self {
-            socket: Arc::new(sock),
-        };
+    pub fn from_std(sock: StdUdpSocket) -> io::Result<Self> {
+        sock.set_nonblocking(true)?;
+        return Ok(Self {
+            io: AsyncFd::new(sock)?,
+        });
     }
 
-    //TODO: somehow implement this
+    /// deregisters the socket from the reactor and returns the underlying std socket, which is
+    /// still in nonblocking mode
     pub fn into_std(self) -> StdUdpSocket {
-        let fd = self.socket.as_raw_fd();
-        return unsafe { StdUdpSocket::from_raw_fd(fd) };
+        return self.io.into_inner();
     }
 
-    pub fn connect<A: ToSocketAddrs>(&self, addr: A) -> Connect<A> {
-        let connect = Connect {
-            socket: self.socket.clone(),
-            addr,
-        };
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().local_addr();
+    }
 
-        return connect;
+    pub fn connect<A: ToSocketAddrs>(&self, addr: A) -> Connect<'_, A> {
+        return Connect { socket: self, addr };
     }
 
-    pub fn recv<'a>(&self, buf: &'a mut [u8]) -> Recv<'a> {
-        let recv = Recv {
-            socket: self.socket.clone(),
+    /// receives a datagram from the connected peer. With the io_uring backend the receive is
+    /// completed by the kernel into a owned buffer, which is copied into `buf` afterwards
+    pub fn recv<'a>(&'a self, buf: &'a mut [u8]) -> Recv<'a> {
+        return Recv {
+            socket: self,
             buf,
+            #[cfg(feature = "io-uring")]
+            op: None,
         };
-        return recv;
     }
 
-    pub fn recv_from<'a>(&self, buf: &'a mut [u8]) -> RecvFrom<'a> {
-        let recv_from = RecvFrom {
-            socket: self.socket.clone(),
-            buf,
-        };
-        return recv_from;
+    pub fn recv_from<'a>(&'a self, buf: &'a mut [u8]) -> RecvFrom<'a> {
+        return RecvFrom { socket: self, buf };
     }
 
-    pub fn send<'a>(&self, buf: &'a [u8]) -> Send<'a> {
-        let send = Send {
-            socket: self.socket.clone(),
+    /// sends `buf` to the connected peer, see [UdpSocket::recv] for the io_uring backend
+    pub fn send<'a>(&'a self, buf: &'a [u8]) -> Send<'a> {
+        return Send {
+            socket: self,
             buf,
+            #[cfg(feature = "io-uring")]
+            op: None,
         };
-        return send;
     }
 
-    pub fn send_to<'a, A>(&self, buf: &'a [u8], addr: A) -> SendTo<'a, A>
+    pub fn send_to<'a, A>(&'a self, buf: &'a [u8], addr: A) -> SendTo<'a, A>
     where
         A: ToSocketAddrs,
     {
-        let send_to = SendTo {
-            socket: self.socket.clone(),
+        return SendTo {
+            socket: self,
             addr,
             buf,
         };
-        return send_to;
     }
 }
 
-pub struct Connect<A> {
-    socket: Arc<StdUdpSocket>,
+pub struct Connect<'a, A> {
+    socket: &'a UdpSocket,
     addr: A,
 }
 
-impl<A> Future for Connect<A>
+impl<A> Future for Connect<'_, A>
 where
     A: ToSocketAddrs,
 {
     type Output = io::Result<()>;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        return match self.socket.connect(&self.addr) {
-            Ok(x) => Poll::Ready(Ok(x)),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                Reactor::get().register(
-                    self.socket.as_raw_fd(),
-                    cx.waker().clone(),
-                    Interest::ReadWrite,
-                );
-                return Poll::Pending;
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        };
+        let socket = self.socket;
+        return socket
+            .io
+            .poll_io(cx, Interest::Write, |sock| sock.connect(&self.addr));
     }
 }
 
 pub struct Recv<'a> {
-    socket: Arc<StdUdpSocket>,
+    socket: &'a UdpSocket,
     buf: &'a mut [u8],
+    #[cfg(feature = "io-uring")]
+    op: Option<Op<Vec<u8>>>,
+}
+
+#[cfg(feature = "io-uring")]
+impl Recv<'_> {
+    fn poll_uring(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let op = match &mut self.op {
+            Some(op) => op,
+            None => {
+                let reactor = self.socket.io.reactor();
+                self.op.insert(uring::recv(
+                    reactor,
+                    self.socket.io.get_ref(),
+                    self.buf.len(),
+                )?)
+            }
+        };
+
+        let res = ready!(Pin::new(op).poll(cx));
+        self.op = None;
+        let (n, data) = res?;
+        self.buf[..n].copy_from_slice(&data[..n]);
+        return Poll::Ready(Ok(n));
+    }
 }
 
 impl Future for Recv<'_> {
     type Output = io::Result<usize>;
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        println!("polling of stuff");
-        return match self.socket.clone().recv(&mut self.buf) {
-            Ok(x) => Poll::Ready(Ok(x)),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                Reactor::get().register(
-                    self.socket.as_raw_fd(),
-                    cx.waker().clone(),
-                    Interest::Read,
-                );
-                return Poll::Pending;
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        };
+        #[cfg(feature = "io-uring")]
+        if self.socket.io.reactor().uring().is_some() {
+            return self.poll_uring(cx);
+        }
+
+        let Recv { socket, buf, .. } = &mut *self;
+        return socket.io.poll_io(cx, Interest::Read, |sock| sock.recv(buf));
     }
 }
 
 pub struct RecvFrom<'a> {
-    socket: Arc<StdUdpSocket>,
+    socket: &'a UdpSocket,
     buf: &'a mut [u8],
 }
 
 impl Future for RecvFrom<'_> {
     type Output = io::Result<(usize, SocketAddr)>;
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        return match self.socket.clone().recv_from(&mut self.buf) {
-            Ok(x) => Poll::Ready(Ok(x)),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                Reactor::get().register(
-                    self.socket.as_raw_fd(),
-                    cx.waker().clone(),
-                    Interest::Read,
-                );
-                return Poll::Pending;
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        };
+        let RecvFrom { socket, buf } = &mut *self;
+        return socket
+            .io
+            .poll_io(cx, Interest::Read, |sock| sock.recv_from(buf));
     }
 }
 
 pub struct Send<'a> {
-    socket: Arc<StdUdpSocket>,
+    socket: &'a UdpSocket,
     buf: &'a [u8],
+    #[cfg(feature = "io-uring")]
+    op: Option<Op<Vec<u8>>>,
+}
+
+#[cfg(feature = "io-uring")]
+impl Send<'_> {
+    fn poll_uring(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let op = match &mut self.op {
+            Some(op) => op,
+            None => {
+                let reactor = self.socket.io.reactor();
+                self.op.insert(uring::send(
+                    reactor,
+                    self.socket.io.get_ref(),
+                    self.buf.to_vec(),
+                )?)
+            }
+        };
+
+        let res = ready!(Pin::new(op).poll(cx));
+        self.op = None;
+        return Poll::Ready(res.map(|(n, _)| n));
+    }
 }
 
 impl Future for Send<'_> {
     type Output = io::Result<usize>;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        return match self.socket.clone().send(&self.buf) {
-            Ok(x) => Poll::Ready(Ok(x)),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                Reactor::get().register(
-                    self.socket.as_raw_fd(),
-                    cx.waker().clone(),
-                    Interest::Write,
-                );
-                return Poll::Pending;
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        };
+        #[cfg(feature = "io-uring")]
+        if self.socket.io.reactor().uring().is_some() {
+            return self.get_mut().poll_uring(cx);
+        }
+
+        let socket = self.socket;
+        return socket
+            .io
+            .poll_io(cx, Interest::Write, |sock| sock.send(self.buf));
     }
 }
 
 pub struct SendTo<'a, A> {
-    socket: Arc<StdUdpSocket>,
+    socket: &'a UdpSocket,
     addr: A,
     buf: &'a [u8],
 }
@@ -192,18 +218,10 @@ where
 {
     type Output = io::Result<usize>;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        return match self.socket.clone().send_to(&self.buf, &self.addr) {
-            Ok(x) => Poll::Ready(Ok(x)),
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                Reactor::get().register(
-                    self.socket.as_raw_fd(),
-                    cx.waker().clone(),
-                    Interest::Write,
-                );
-                return Poll::Pending;
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        };
+        let socket = self.socket;
+        return socket.io.poll_io(cx, Interest::Write, |sock| {
+            sock.send_to(self.buf, &self.addr)
+        });
     }
 }
 
@@ -211,23 +229,26 @@ where
 mod test {
     use std::assert_eq;
 
-    use crate::prelude::{Executor, Runtime};
+    use crate::prelude::Runtime;
+    use crate::test_util::Join;
 
     use super::UdpSocket;
     use std::net::UdpSocket as StdUdpSocket;
 
     #[test]
     pub fn bind() {
-        let _ = UdpSocket::bind("127.0.0.1:3000").unwrap();
+        let rt = Runtime::new();
+        let _enter = rt.enter();
+        let _ = UdpSocket::bind("127.0.0.1:0").unwrap();
     }
 
     #[test]
     pub fn send() {
         let mut rt = Runtime::new();
         rt.block_on(async {
-            let sock1 = StdUdpSocket::bind("127.0.0.1:3000").unwrap();
-            let sock2 = UdpSocket::bind("127.0.0.1:3001").unwrap();
-            sock2.connect("127.0.0.1:3000").await.unwrap();
+            let sock1 = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+            let sock2 = UdpSocket::bind("127.0.0.1:0").unwrap();
+            sock2.connect(sock1.local_addr().unwrap()).await.unwrap();
             let buf = "hello".as_bytes();
             let n = sock2.send(buf).await.unwrap();
             assert_eq!(n, 5);
@@ -242,10 +263,13 @@ mod test {
     pub fn send_to() {
         let mut rt = Runtime::new();
         rt.block_on(async {
-            let sock1 = StdUdpSocket::bind("127.0.0.1:3002").unwrap();
-            let sock2 = UdpSocket::bind("127.0.0.1:3003").unwrap();
+            let sock1 = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+            let sock2 = UdpSocket::bind("127.0.0.1:0").unwrap();
             let buf = "hello".as_bytes();
-            let n = sock2.send_to(buf, "127.0.0.1:3002").await.unwrap();
+            let n = sock2
+                .send_to(buf, sock1.local_addr().unwrap())
+                .await
+                .unwrap();
             assert_eq!(n, 5);
             let mut buf2 = [0; 5];
             let n = sock1.recv(&mut buf2).unwrap();
@@ -259,11 +283,13 @@ mod test {
         let mut rt = Runtime::new();
 
         rt.block_on(async {
-            let sock = UdpSocket::bind("127.0.0.1:3004").unwrap();
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
             let mut buf = [0; 5];
 
-            let sock2 = std::net::UdpSocket::bind("127.0.0.1:3005").unwrap();
-            let n = sock2.send_to("hello".as_bytes(), "127.0.0.1:3004").unwrap();
+            let sock2 = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            let n = sock2
+                .send_to("hello".as_bytes(), sock.local_addr().unwrap())
+                .unwrap();
             assert_eq!(n, 5);
 
             let n = sock.recv(&mut buf).await.unwrap();
@@ -271,4 +297,177 @@ mod test {
             assert_eq!(buf, "hello".as_bytes());
         });
     }
+
+    #[test]
+    pub fn recv_current_thread() {
+        let mut rt = crate::runtime::runtime::RuntimeBuilder::current_thread().build();
+
+        rt.block_on(async {
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let addr = sock.local_addr().unwrap();
+            let (tx, rx) = std::sync::mpsc::channel();
+            let sender = std::thread::spawn(move || {
+                rx.recv().unwrap();
+                let sock2 = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+                sock2.send_to("hello".as_bytes(), addr).unwrap();
+            });
+
+            // the receive is already waiting when the datagram gets sent
+            let mut buf = [0; 5];
+            let (n, ()) = Join::new(sock.recv(&mut buf), async { tx.send(()).unwrap() }).await;
+            assert_eq!(n.unwrap(), 5);
+            assert_eq!(buf, "hello".as_bytes());
+            sender.join().unwrap();
+        });
+    }
+
+    #[test]
+    pub fn recv_and_send_concurrently() {
+        let mut rt = Runtime::new();
+
+        rt.block_on(async {
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let peer = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+            sock.connect(peer.local_addr().unwrap()).await.unwrap();
+
+            // the pending recv must not lose its waker to the send on the same socket
+            let mut buf = [0; 5];
+            let recv = sock.recv(&mut buf);
+            let send = async {
+                assert_eq!(sock.send(b"ping!").await.unwrap(), 5);
+                let mut ping = [0; 5];
+                let (_, addr) = peer.recv_from(&mut ping).unwrap();
+                peer.send_to(b"pong!", addr).unwrap();
+            };
+            let (n, ()) = Join::new(recv, send).await;
+            assert_eq!(n.unwrap(), 5);
+            assert_eq!(&buf, b"pong!");
+        });
+    }
+
+    #[test]
+    pub fn recv_poll_backend() {
+        use crate::runtime::{reactor::poller::Backend, runtime::RuntimeBuilder};
+
+        let mut rt = RuntimeBuilder::new()
+            .threads(2)
+            .backend(Backend::Poll)
+            .build();
+
+        rt.block_on(async {
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let addr = sock.local_addr().unwrap();
+            let (tx, rx) = std::sync::mpsc::channel();
+            let sender = std::thread::spawn(move || {
+                rx.recv().unwrap();
+                let sock2 = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+                sock2.send_to("hello".as_bytes(), addr).unwrap();
+            });
+
+            let mut buf = [0; 5];
+            let (n, ()) = Join::new(sock.recv(&mut buf), async { tx.send(()).unwrap() }).await;
+            assert_eq!(n.unwrap(), 5);
+            assert_eq!(buf, "hello".as_bytes());
+            sender.join().unwrap();
+        });
+    }
+
+    #[cfg(feature = "io-uring")]
+    #[test]
+    pub fn send_and_recv_uring_backend() {
+        use crate::runtime::{reactor::poller::Backend, runtime::RuntimeBuilder};
+
+        let mut rt = RuntimeBuilder::new()
+            .threads(2)
+            .backend(Backend::IoUring)
+            .build();
+
+        rt.block_on(async {
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let sock2 = UdpSocket::bind("127.0.0.1:0").unwrap();
+            sock.connect(sock2.local_addr().unwrap()).await.unwrap();
+            sock2.connect(sock.local_addr().unwrap()).await.unwrap();
+
+            // the receive is submitted before the send
+            let mut buf = [0; 5];
+            let (n, sent) = Join::new(sock2.recv(&mut buf), sock.send("hello".as_bytes())).await;
+            assert_eq!(sent.unwrap(), 5);
+            assert_eq!(&buf[..n.unwrap()], "hello".as_bytes());
+        });
+    }
+
+    #[test]
+    pub fn recv_after_runtime_dropped() {
+        let rt = Runtime::new();
+        let sock = {
+            let _enter = rt.enter();
+            UdpSocket::bind("127.0.0.1:0").unwrap()
+        };
+        drop(rt);
+
+        // the reactor of the socket is gone, so waiting for it must fail instead of hanging
+        let mut rt = Runtime::new();
+        let mut buf = [0; 5];
+        assert!(rt.block_on(sock.recv(&mut buf)).is_err());
+    }
+
+    #[cfg(feature = "io-uring")]
+    #[test]
+    pub fn recv_after_runtime_dropped_uring_backend() {
+        use std::future::Future;
+        use std::task::{Context, Waker};
+
+        use crate::runtime::{reactor::poller::Backend, runtime::RuntimeBuilder};
+
+        let rt = RuntimeBuilder::new()
+            .threads(1)
+            .backend(Backend::IoUring)
+            .build();
+        let sock = {
+            let _enter = rt.enter();
+            UdpSocket::bind("127.0.0.1:0").unwrap()
+        };
+
+        // the receive is submitted to the ring before the runtime goes away
+        let mut buf = [0; 5];
+        let mut recv = Box::pin(sock.recv(&mut buf));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(recv.as_mut().poll(&mut cx).is_pending());
+        drop(rt);
+
+        let mut rt = Runtime::new();
+        assert!(rt.block_on(recv).is_err());
+        assert!(rt.block_on(sock.recv(&mut [0; 5])).is_err());
+    }
+
+    #[cfg(feature = "io-uring")]
+    #[test]
+    pub fn send_completed_before_runtime_dropped_uring_backend() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        use crate::runtime::{reactor::poller::Backend, runtime::RuntimeBuilder};
+
+        let mut rt = RuntimeBuilder::new()
+            .threads(1)
+            .backend(Backend::IoUring)
+            .build();
+        let peer = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+        let sock = {
+            let _enter = rt.enter();
+            UdpSocket::bind("127.0.0.1:0").unwrap()
+        };
+        rt.block_on(sock.connect(peer.local_addr().unwrap()))
+            .unwrap();
+
+        // the kernel completes the send right away, only nobody reaped the completion yet
+        let mut send = Box::pin(sock.send(b"hello"));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        let mut buf = [0; 5];
+        assert_eq!(peer.recv(&mut buf).unwrap(), 5);
+        drop(rt);
+
+        assert!(matches!(send.as_mut().poll(&mut cx), Poll::Ready(Ok(5))));
+    }
 }
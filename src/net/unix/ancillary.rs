@@ -0,0 +1,154 @@
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::ptr;
+use std::task::{Context, Poll};
+
+use crate::io::async_fd::AsyncFd;
+use crate::runtime::reactor::interest::Interest;
+
+/// the most fds the kernel passes with a single message (`SCM_MAX_FD`), so the control buffer
+/// is large enough that no received fd gets dropped
+const MAX_FDS: usize = 253;
+
+/// Sends data together with fds, see
+/// [UnixStream::send_with_fds](super::stream::UnixStream::send_with_fds)
+pub struct SendWithFds<'a, T: AsRawFd> {
+    io: &'a AsyncFd<T>,
+    buf: &'a [u8],
+    fds: &'a [BorrowedFd<'a>],
+}
+
+impl<'a, T: AsRawFd> SendWithFds<'a, T> {
+    pub(crate) fn new(io: &'a AsyncFd<T>, buf: &'a [u8], fds: &'a [BorrowedFd<'a>]) -> Self {
+        return Self { io, buf, fds };
+    }
+}
+
+impl<T: AsRawFd> Future for SendWithFds<'_, T> {
+    type Output = io::Result<usize>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // the fds are attached to the sent bytes, without any the peer never sees them
+        if self.buf.is_empty() && !self.fds.is_empty() {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "passing fds needs at least one byte to attach them to",
+            )));
+        }
+        return self.io.poll_io(cx, Interest::Write, |socket| {
+            send_with_fds(socket.as_raw_fd(), self.buf, self.fds)
+        });
+    }
+}
+
+/// The fds received along with some bytes, see [RecvWithFds]
+#[derive(Debug)]
+pub struct ReceivedFds {
+    /// the fds in the order they were sent, they are close-on-exec
+    pub fds: Vec<OwnedFd>,
+    /// whether the kernel dropped fds it couldn't fit or install, for example because the
+    /// process ran out of them. The bytes and the fds that did arrive are still returned
+    pub truncated: bool,
+}
+
+/// Receives data together with fds, see
+/// [UnixStream::recv_with_fds](super::stream::UnixStream::recv_with_fds)
+pub struct RecvWithFds<'a, T: AsRawFd> {
+    io: &'a AsyncFd<T>,
+    buf: &'a mut [u8],
+}
+
+impl<'a, T: AsRawFd> RecvWithFds<'a, T> {
+    pub(crate) fn new(io: &'a AsyncFd<T>, buf: &'a mut [u8]) -> Self {
+        return Self { io, buf };
+    }
+}
+
+impl<T: AsRawFd> Future for RecvWithFds<'_, T> {
+    type Output = io::Result<(usize, ReceivedFds)>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let RecvWithFds { io, buf } = &mut *self;
+        return io.poll_io(cx, Interest::Read, |socket| {
+            recv_with_fds(socket.as_raw_fd(), buf)
+        });
+    }
+}
+
+/// a control buffer of at least `len` bytes, aligned for `cmsghdr`
+fn control_buffer(len: usize) -> Vec<u64> {
+    return vec![0; len.div_ceil(mem::size_of::<u64>())];
+}
+
+fn send_with_fds(fd: RawFd, buf: &[u8], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let payload = mem::size_of_val(fds) as libc::c_uint;
+    let space = unsafe { libc::CMSG_SPACE(payload) } as usize;
+    let mut control = control_buffer(space);
+
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if !fds.is_empty() {
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = space as _;
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(payload) as _;
+            let data = libc::CMSG_DATA(cmsg) as *mut RawFd;
+            for (i, fd) in fds.iter().enumerate() {
+                ptr::write_unaligned(data.add(i), fd.as_raw_fd());
+            }
+        }
+    }
+
+    let n = unsafe { libc::sendmsg(fd, &msg, libc::MSG_NOSIGNAL) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    return Ok(n as usize);
+}
+
+fn recv_with_fds(fd: RawFd, buf: &mut [u8]) -> io::Result<(usize, ReceivedFds)> {
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let space = unsafe { libc::CMSG_SPACE((MAX_FDS * mem::size_of::<RawFd>()) as libc::c_uint) };
+    let mut control = control_buffer(space as usize);
+
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = space as _;
+
+    let n = unsafe { libc::recvmsg(fd, &mut msg, libc::MSG_CMSG_CLOEXEC) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut fds = Vec::new();
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+                let len = ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize)
+                    / mem::size_of::<RawFd>();
+                for i in 0..len {
+                    fds.push(OwnedFd::from_raw_fd(ptr::read_unaligned(data.add(i))));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    let truncated = msg.msg_flags & libc::MSG_CTRUNC != 0;
+    return Ok((n as usize, ReceivedFds { fds, truncated }));
+}
@@ -0,0 +1,280 @@
+use std::future::Future;
+use std::io;
+use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
+use std::os::unix::net::{SocketAddr, UnixDatagram as StdUnixDatagram};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::ancillary::{RecvWithFds, SendWithFds};
+use super::ucred::{self, UCred};
+use crate::io::async_fd::AsyncFd;
+use crate::runtime::reactor::interest::Interest;
+
+/// A unix datagram socket registered with the reactor of the runtime it was created in
+///
+/// # Panics
+/// creating it outside of the context of a runtime panics, see
+/// [Handle::current](crate::runtime::context::Handle::current)
+pub struct UnixDatagram {
+    io: AsyncFd<StdUnixDatagram>,
+}
+
+impl UnixDatagram {
+    /// binds to `path`, which must not exist yet
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixDatagram> {
+        return Self::from_std(StdUnixDatagram::bind(path)?);
+    }
+
+    /// binds to `addr`, which can also be in the abstract namespace, see
+    /// [SocketAddrExt](std::os::linux::net::SocketAddrExt)
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixDatagram> {
+        return Self::from_std(StdUnixDatagram::bind_addr(addr)?);
+    }
+
+    /// creates a socket that isn't bound to any address
+    pub fn unbound() -> io::Result<UnixDatagram> {
+        return Self::from_std(StdUnixDatagram::unbound()?);
+    }
+
+    /// creates a pair of connected sockets
+    pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = StdUnixDatagram::pair()?;
+        return Ok((Self::from_std(a)?, Self::from_std(b)?));
+    }
+
+    pub fn from_std(socket: StdUnixDatagram) -> io::Result<Self> {
+        socket.set_nonblocking(true)?;
+        return Ok(Self {
+            io: AsyncFd::new(socket)?,
+        });
+    }
+
+    /// deregisters the socket from the reactor and returns the underlying std socket, which is
+    /// still in nonblocking mode
+    pub fn into_std(self) -> StdUnixDatagram {
+        return self.io.into_inner();
+    }
+
+    /// sets the default peer of [UnixDatagram::send] and the only one [UnixDatagram::recv]
+    /// receives from
+    pub fn connect<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        return self.io.get_ref().connect(path);
+    }
+
+    pub fn connect_addr(&self, addr: &SocketAddr) -> io::Result<()> {
+        return self.io.get_ref().connect_addr(addr);
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().local_addr();
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().peer_addr();
+    }
+
+    /// the credentials of the peer process, only available for sockets created by
+    /// [UnixDatagram::pair]
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        return ucred::peer_cred(self.io.get_ref());
+    }
+
+    /// sends `buf` to the connected peer and passes `fds` along with `SCM_RIGHTS`, see
+    /// [UnixStream::send_with_fds](super::stream::UnixStream::send_with_fds)
+    pub fn send_with_fds<'a>(
+        &'a self,
+        buf: &'a [u8],
+        fds: &'a [BorrowedFd<'a>],
+    ) -> SendWithFds<'a, StdUnixDatagram> {
+        return SendWithFds::new(&self.io, buf, fds);
+    }
+
+    /// receives a datagram together with the fds passed along with it, see
+    /// [UnixStream::recv_with_fds](super::stream::UnixStream::recv_with_fds)
+    pub fn recv_with_fds<'a>(&'a self, buf: &'a mut [u8]) -> RecvWithFds<'a, StdUnixDatagram> {
+        return RecvWithFds::new(&self.io, buf);
+    }
+
+    pub fn recv<'a>(&'a self, buf: &'a mut [u8]) -> Recv<'a> {
+        return Recv { socket: self, buf };
+    }
+
+    pub fn recv_from<'a>(&'a self, buf: &'a mut [u8]) -> RecvFrom<'a> {
+        return RecvFrom { socket: self, buf };
+    }
+
+    pub fn send<'a>(&'a self, buf: &'a [u8]) -> Send<'a> {
+        return Send { socket: self, buf };
+    }
+
+    pub fn send_to<'a, P>(&'a self, buf: &'a [u8], path: P) -> SendTo<'a, P>
+    where
+        P: AsRef<Path>,
+    {
+        return SendTo {
+            socket: self,
+            path,
+            buf,
+        };
+    }
+}
+
+impl AsRawFd for UnixDatagram {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.io.get_ref().as_raw_fd();
+    }
+}
+
+pub struct Recv<'a> {
+    socket: &'a UnixDatagram,
+    buf: &'a mut [u8],
+}
+
+impl Future for Recv<'_> {
+    type Output = io::Result<usize>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Recv { socket, buf } = &mut *self;
+        return socket
+            .io
+            .poll_io(cx, Interest::Read, |socket| socket.recv(buf));
+    }
+}
+
+pub struct RecvFrom<'a> {
+    socket: &'a UnixDatagram,
+    buf: &'a mut [u8],
+}
+
+impl Future for RecvFrom<'_> {
+    type Output = io::Result<(usize, SocketAddr)>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let RecvFrom { socket, buf } = &mut *self;
+        return socket
+            .io
+            .poll_io(cx, Interest::Read, |socket| socket.recv_from(buf));
+    }
+}
+
+pub struct Send<'a> {
+    socket: &'a UnixDatagram,
+    buf: &'a [u8],
+}
+
+impl Future for Send<'_> {
+    type Output = io::Result<usize>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let socket = self.socket;
+        return socket
+            .io
+            .poll_io(cx, Interest::Write, |socket| socket.send(self.buf));
+    }
+}
+
+pub struct SendTo<'a, P> {
+    socket: &'a UnixDatagram,
+    path: P,
+    buf: &'a [u8],
+}
+
+impl<P> Future for SendTo<'_, P>
+where
+    P: AsRef<Path>,
+{
+    type Output = io::Result<usize>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let socket = self.socket;
+        return socket.io.poll_io(cx, Interest::Write, |socket| {
+            socket.send_to(self.buf, &self.path)
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UnixDatagram;
+    use crate::prelude::Runtime;
+    use crate::test_util::{temp_path, Join};
+
+    #[test]
+    fn pair() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (a, b) = UnixDatagram::pair().unwrap();
+            assert_eq!(
+                a.peer_cred().unwrap().pid(),
+                std::process::id() as libc::pid_t
+            );
+
+            // the receive is already waiting when the datagram gets sent
+            let mut buf = [0; 5];
+            let (n, sent) = Join::new(b.recv(&mut buf), a.send(b"hello")).await;
+            assert_eq!(sent.unwrap(), 5);
+            assert_eq!(&buf[..n.unwrap()], b"hello");
+        });
+    }
+
+    #[test]
+    fn send_to_path() {
+        let server_path = temp_path("dgram-server.sock");
+        let client_path = temp_path("dgram-client.sock");
+        let _ = std::fs::remove_file(&server_path);
+        let _ = std::fs::remove_file(&client_path);
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let server = UnixDatagram::bind(&server_path).unwrap();
+            let client = UnixDatagram::bind(&client_path).unwrap();
+            client.send_to(b"ping", &server_path).await.unwrap();
+
+            let mut buf = [0; 4];
+            let (n, addr) = server.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"ping");
+            assert_eq!(addr.as_pathname(), Some(client_path.as_path()));
+
+            let unbound = UnixDatagram::unbound().unwrap();
+            unbound.connect(&server_path).unwrap();
+            unbound.send(b"pong").await.unwrap();
+            let n = server.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"pong");
+        });
+        std::fs::remove_file(server_path).unwrap();
+        std::fs::remove_file(client_path).unwrap();
+    }
+
+    #[test]
+    fn pass_udp_sockets() {
+        use std::net::UdpSocket as StdUdpSocket;
+        use std::os::fd::AsFd;
+
+        use crate::net::udp::UdpSocket;
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (a, b) = UnixDatagram::pair().unwrap();
+            let first = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+            let second = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+            let addrs = [first.local_addr().unwrap(), second.local_addr().unwrap()];
+            a.send_with_fds(b"udp", &[first.as_fd(), second.as_fd()])
+                .await
+                .unwrap();
+            drop((first, second));
+
+            let mut buf = [0; 8];
+            let (n, received) = b.recv_with_fds(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"udp");
+            assert!(!received.truncated);
+            assert_eq!(received.fds.len(), 2);
+
+            let sockets = received
+                .fds
+                .into_iter()
+                .map(|fd| UdpSocket::from_std(StdUdpSocket::from(fd)).unwrap())
+                .collect::<Vec<_>>();
+            sockets[0].send_to(b"hi", addrs[1]).await.unwrap();
+            let (n, from) = sockets[1].recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"hi");
+            assert_eq!(from, addrs[0]);
+        });
+    }
+}
@@ -0,0 +1,158 @@
+use std::future::Future;
+use std::io;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::net::{SocketAddr, UnixListener as StdUnixListener};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use super::stream::UnixStream;
+use crate::io::async_fd::AsyncFd;
+use crate::io::stream::Stream;
+use crate::runtime::reactor::interest::Interest;
+
+/// A unix socket listening for connections, registered with the reactor of the runtime it was
+/// created in
+///
+/// # Panics
+/// creating it outside of the context of a runtime panics, see
+/// [Handle::current](crate::runtime::context::Handle::current)
+pub struct UnixListener {
+    io: AsyncFd<StdUnixListener>,
+}
+
+impl UnixListener {
+    /// binds to `path`, which must not exist yet
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixListener> {
+        return Self::from_std(StdUnixListener::bind(path)?);
+    }
+
+    /// binds to `addr`, which can also be in the abstract namespace, see
+    /// [SocketAddrExt](std::os::linux::net::SocketAddrExt)
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixListener> {
+        return Self::from_std(StdUnixListener::bind_addr(addr)?);
+    }
+
+    pub fn from_std(listener: StdUnixListener) -> io::Result<Self> {
+        listener.set_nonblocking(true)?;
+        return Ok(Self {
+            io: AsyncFd::with_interest(listener, Interest::Read)?,
+        });
+    }
+
+    /// deregisters the listener from the reactor and returns the underlying std listener, which
+    /// is still in nonblocking mode
+    pub fn into_std(self) -> StdUnixListener {
+        return self.io.into_inner();
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().local_addr();
+    }
+
+    /// waits for a new connection, errors are returned and the listener stays usable after them
+    pub fn accept(&self) -> Accept<'_> {
+        return Accept { listener: self };
+    }
+
+    /// the accepted connections as a [Stream], which never ends
+    pub fn incoming(&self) -> Incoming<'_> {
+        return Incoming { listener: self };
+    }
+
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(UnixStream, SocketAddr)>> {
+        let (stream, addr) = ready!(self
+            .io
+            .poll_io(cx, Interest::Read, |listener| listener.accept()))?;
+        return Poll::Ready(Ok((UnixStream::from_std(stream)?, addr)));
+    }
+}
+
+impl AsRawFd for UnixListener {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.io.get_ref().as_raw_fd();
+    }
+}
+
+pub struct Accept<'a> {
+    listener: &'a UnixListener,
+}
+
+impl Future for Accept<'_> {
+    type Output = io::Result<(UnixStream, SocketAddr)>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        return self.listener.poll_accept(cx);
+    }
+}
+
+pub struct Incoming<'a> {
+    listener: &'a UnixListener,
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<UnixStream>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = ready!(self.listener.poll_accept(cx));
+        return Poll::Ready(Some(res.map(|(stream, _)| stream)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::SocketAddr;
+
+    use super::UnixListener;
+    use crate::io::{read::AsyncReadExt, stream::StreamExt, write::AsyncWriteExt};
+    use crate::net::unix::stream::UnixStream;
+    use crate::prelude::Runtime;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn accept_on_path() {
+        let path = temp_path("accept.sock");
+        let _ = std::fs::remove_file(&path);
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let listener = UnixListener::bind(&path).unwrap();
+            assert_eq!(
+                listener.local_addr().unwrap().as_pathname(),
+                Some(path.as_path())
+            );
+
+            let mut client = UnixStream::connect(&path).await.unwrap();
+            let (mut server, _) = listener.accept().await.unwrap();
+            client.write(b"hi").await.unwrap();
+
+            let mut buf = [0; 2];
+            let mut read = 0;
+            while read < 2 {
+                read += server.read(&mut buf[read..]).await.unwrap();
+            }
+            assert_eq!(&buf, b"hi");
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn incoming_in_abstract_namespace() {
+        let name = format!("oxic-incoming-{}", std::process::id());
+        let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let listener = UnixListener::bind_addr(&addr).unwrap();
+            let local = listener.local_addr().unwrap();
+            assert_eq!(local.as_abstract_name(), Some(name.as_bytes()));
+
+            let _first = UnixStream::connect_addr(&addr).await.unwrap();
+            let _second = UnixStream::connect_addr(&addr).await.unwrap();
+            let mut incoming = listener.incoming();
+            for _ in 0..2 {
+                let stream = incoming.next().await.unwrap().unwrap();
+                assert!(stream.peer_cred().is_ok());
+            }
+        });
+    }
+}
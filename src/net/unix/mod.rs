@@ -0,0 +1,5 @@
+pub mod ancillary;
+pub mod datagram;
+pub mod listener;
+pub mod stream;
+pub mod ucred;
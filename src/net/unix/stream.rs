@@ -0,0 +1,270 @@
+use std::future::poll_fn;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
+use std::os::unix::net::{SocketAddr, UnixStream as StdUnixStream};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use super::ancillary::{RecvWithFds, SendWithFds};
+use super::ucred::{self, UCred};
+use crate::io::async_fd::AsyncFd;
+use crate::io::{read::AsyncRead, write::AsyncWrite};
+use crate::net::sys;
+use crate::runtime::reactor::interest::Interest;
+
+/// A unix stream socket registered with the reactor of the runtime it was created in
+///
+/// # Panics
+/// creating it outside of the context of a runtime panics, see
+/// [Handle::current](crate::runtime::context::Handle::current)
+pub struct UnixStream {
+    io: AsyncFd<StdUnixStream>,
+}
+
+impl UnixStream {
+    /// connects to the socket at `path`. Connecting to a unix socket completes right away,
+    /// unless the backlog of the listener is full. The kernel doesn't queue the connect then and
+    /// nothing signals once there is room again, so it fails with
+    /// [WouldBlock](io::ErrorKind::WouldBlock) like it does for std
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<UnixStream> {
+        return Self::connect_addr(&SocketAddr::from_pathname(path)?).await;
+    }
+
+    /// connects to `addr`, which can also be in the abstract namespace, see
+    /// [SocketAddrExt](std::os::linux::net::SocketAddrExt)
+    pub async fn connect_addr(addr: &SocketAddr) -> io::Result<UnixStream> {
+        let (storage, len) = sys::unix_sockaddr(addr)?;
+        let fd = sys::socket(libc::AF_UNIX, libc::SOCK_STREAM)?;
+        let res = unsafe {
+            libc::connect(
+                fd.as_raw_fd(),
+                &storage as *const libc::sockaddr_un as *const libc::sockaddr,
+                len,
+            )
+        };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EINPROGRESS) {
+                return Err(err);
+            }
+        }
+
+        let stream = Self::from_std(StdUnixStream::from(fd))?;
+        poll_fn(|cx| stream.poll_connected(cx)).await?;
+        return Ok(stream);
+    }
+
+    /// resolves once a non-blocking connect finished, writability alone doesn't tell whether it
+    /// succeeded so the pending error of the socket is checked afterwards
+    fn poll_connected(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.io.poll_write_ready(cx))?;
+            if let Some(e) = self.io.get_ref().take_error()? {
+                return Poll::Ready(Err(e));
+            }
+
+            match self.io.get_ref().peer_addr() {
+                Ok(_) => return Poll::Ready(Ok(())),
+                // the readiness was the optimistic one of a new registration
+                Err(e) if e.raw_os_error() == Some(libc::ENOTCONN) => guard.clear_ready(),
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+
+    /// creates a pair of connected streams
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        let (a, b) = StdUnixStream::pair()?;
+        return Ok((Self::from_std(a)?, Self::from_std(b)?));
+    }
+
+    pub fn from_std(stream: StdUnixStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        return Ok(Self {
+            io: AsyncFd::new(stream)?,
+        });
+    }
+
+    /// deregisters the stream from the reactor and returns the underlying std stream, which is
+    /// still in nonblocking mode
+    pub fn into_std(self) -> StdUnixStream {
+        return self.io.into_inner();
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().local_addr();
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        return self.io.get_ref().peer_addr();
+    }
+
+    /// the credentials of the peer process
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        return ucred::peer_cred(self.io.get_ref());
+    }
+
+    /// shuts down the read half, the write half or both halves of the connection
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        return self.io.get_ref().shutdown(how);
+    }
+
+    /// sends `buf` and passes `fds` to the peer with `SCM_RIGHTS`. `buf` has to hold at least
+    /// one byte, the fds are attached to it, otherwise [InvalidInput](io::ErrorKind::InvalidInput)
+    /// is returned
+    pub fn send_with_fds<'a>(
+        &'a self,
+        buf: &'a [u8],
+        fds: &'a [BorrowedFd<'a>],
+    ) -> SendWithFds<'a, StdUnixStream> {
+        return SendWithFds::new(&self.io, buf, fds);
+    }
+
+    /// receives into `buf` together with the fds attached to the received bytes, they can be
+    /// turned into sockets through `from_std`. Check
+    /// [ReceivedFds::truncated](super::ancillary::ReceivedFds::truncated) to learn whether
+    /// the kernel had to drop some of them
+    pub fn recv_with_fds<'a>(&'a self, buf: &'a mut [u8]) -> RecvWithFds<'a, StdUnixStream> {
+        return RecvWithFds::new(&self.io, buf);
+    }
+}
+
+impl AsRawFd for UnixStream {
+    fn as_raw_fd(&self) -> RawFd {
+        return self.io.get_ref().as_raw_fd();
+    }
+}
+
+impl AsyncRead for UnixStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        return this
+            .io
+            .poll_io(cx, Interest::Read, |mut stream| stream.read(buf));
+    }
+}
+
+impl AsyncWrite for UnixStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        return this
+            .io
+            .poll_io(cx, Interest::Write, |mut stream| stream.write(buf));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UnixStream;
+    use crate::io::{read::AsyncReadExt, write::AsyncWriteExt};
+    use crate::prelude::Runtime;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn pair() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (mut a, mut b) = UnixStream::pair().unwrap();
+            assert_eq!(a.write(b"hello").await.unwrap(), 5);
+
+            let mut buf = [0; 5];
+            let mut read = 0;
+            while read < 5 {
+                read += b.read(&mut buf[read..]).await.unwrap();
+            }
+            assert_eq!(&buf, b"hello");
+        });
+    }
+
+    #[test]
+    fn peer_cred() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (a, _b) = UnixStream::pair().unwrap();
+            let cred = a.peer_cred().unwrap();
+            assert_eq!(cred.pid(), std::process::id() as libc::pid_t);
+            assert_eq!(cred.uid(), unsafe { libc::getuid() });
+            assert_eq!(cred.gid(), unsafe { libc::getgid() });
+        });
+    }
+
+    #[test]
+    fn connect_with_full_backlog() {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::net::{UnixListener as StdUnixListener, UnixStream as StdUnixStream};
+
+        let path = temp_path("backlog.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener = StdUnixListener::bind(&path).unwrap();
+        // a backlog of 0 only fits a single pending connection
+        assert_eq!(unsafe { libc::listen(listener.as_raw_fd(), 0) }, 0);
+        let _first = StdUnixStream::connect(&path).unwrap();
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let err = UnixStream::connect(&path).await.err().unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+            listener.accept().unwrap();
+            let mut stream = UnixStream::connect(&path).await.unwrap();
+            let (mut accepted, _) = listener.accept().unwrap();
+            stream.write(b"hi").await.unwrap();
+            let mut buf = [0; 2];
+            std::io::Read::read_exact(&mut accepted, &mut buf).unwrap();
+            assert_eq!(&buf, b"hi");
+        });
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fds_need_a_byte() {
+        use std::os::fd::AsFd;
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (a, _b) = UnixStream::pair().unwrap();
+            let passed = std::fs::File::open("/dev/null").unwrap();
+            let err = a.send_with_fds(b"", &[passed.as_fd()]).await.unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        });
+    }
+
+    #[test]
+    fn pass_listener() {
+        use std::net::TcpListener as StdTcpListener;
+        use std::os::fd::AsFd;
+
+        use crate::net::tcp::{TcpListener, TcpStream};
+
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let (a, b) = UnixStream::pair().unwrap();
+            let listener = StdTcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            assert_eq!(a.send_with_fds(b"l", &[listener.as_fd()]).await.unwrap(), 1);
+            drop(listener);
+
+            let mut buf = [0; 1];
+            let (n, mut received) = b.recv_with_fds(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"l");
+            assert!(!received.truncated);
+            assert_eq!(received.fds.len(), 1);
+
+            let fd = received.fds.remove(0);
+            let listener = TcpListener::from_std(StdTcpListener::from(fd)).unwrap();
+            assert_eq!(listener.local_addr().unwrap(), addr);
+            let _client = TcpStream::connect(addr).await.unwrap();
+            listener.accept().await.unwrap();
+        });
+    }
+}
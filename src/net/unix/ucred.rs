@@ -0,0 +1,37 @@
+use std::io;
+use std::os::fd::AsRawFd;
+
+use crate::net::sys;
+
+/// The credentials of the process on the other end of a unix socket, as they were when it
+/// connected the socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UCred {
+    pid: libc::pid_t,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+}
+
+impl UCred {
+    pub fn pid(&self) -> libc::pid_t {
+        return self.pid;
+    }
+
+    pub fn uid(&self) -> libc::uid_t {
+        return self.uid;
+    }
+
+    pub fn gid(&self) -> libc::gid_t {
+        return self.gid;
+    }
+}
+
+/// reads `SO_PEERCRED` of `fd`
+pub(crate) fn peer_cred(fd: &impl AsRawFd) -> io::Result<UCred> {
+    let cred: libc::ucred = sys::getsockopt(fd, libc::SOL_SOCKET, libc::SO_PEERCRED)?;
+    return Ok(UCred {
+        pid: cred.pid,
+        uid: cred.uid,
+        gid: cred.gid,
+    });
+}
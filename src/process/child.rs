@@ -0,0 +1,302 @@
+use std::{
+    future::poll_fn,
+    io,
+    os::fd::{FromRawFd, OwnedFd},
+    process::{Child as StdChild, ExitStatus},
+    task::{ready, Context, Poll},
+};
+
+use super::{orphan, sigchld};
+use crate::io::{
+    async_fd::AsyncFd,
+    pipe::{Receiver, Sender},
+};
+use crate::runtime::reactor::interest::Interest;
+
+/// How the exit of a child is noticed
+enum Reaper {
+    /// a pidfd registered with the reactor, it becomes readable once the child exited
+    Pidfd(AsyncFd<OwnedFd>),
+    /// kernels without `pidfd_open` only tell through `SIGCHLD`
+    Sigchld,
+}
+
+/// A child process spawned by [Command](super::command::Command). Dropping it without waiting
+/// leaves the child running unless it was spawned with `kill_on_drop`, either way it gets reaped
+/// in the background once it exited
+pub struct Child {
+    child: StdChild,
+    reaper: Reaper,
+    kill_on_drop: bool,
+    status: Option<ExitStatus>,
+    /// the stdin of the child if it was piped, dropping it closes the pipe
+    pub stdin: Option<Sender>,
+    /// the stdout of the child if it was piped
+    pub stdout: Option<Receiver>,
+    /// the stderr of the child if it was piped
+    pub stderr: Option<Receiver>,
+}
+
+impl Child {
+    pub(crate) fn new(child: StdChild, kill_on_drop: bool) -> io::Result<Self> {
+        // the child is running already, if the pidfd is of no use it is still waited for
+        let reaper = match pidfd_open(child.id())
+            .and_then(|fd| AsyncFd::with_interest(fd, Interest::Read))
+        {
+            Ok(pidfd) => Reaper::Pidfd(pidfd),
+            Err(_) => Reaper::Sigchld,
+        };
+        return Self::with_reaper(child, reaper, kill_on_drop);
+    }
+
+    fn with_reaper(mut child: StdChild, reaper: Reaper, kill_on_drop: bool) -> io::Result<Self> {
+        let stdin = child.stdin.take().map(OwnedFd::from);
+        let stdout = child.stdout.take().map(OwnedFd::from);
+        let stderr = child.stderr.take().map(OwnedFd::from);
+        return Ok(Self {
+            child,
+            reaper,
+            kill_on_drop,
+            status: None,
+            stdin: stdin.map(Sender::from_owned_fd).transpose()?,
+            stdout: stdout.map(Receiver::from_owned_fd).transpose()?,
+            stderr: stderr.map(Receiver::from_owned_fd).transpose()?,
+        });
+    }
+
+    /// the process id, `None` once the child was waited for
+    pub fn id(&self) -> Option<u32> {
+        return match self.status {
+            Some(_) => None,
+            None => Some(self.child.id()),
+        };
+    }
+
+    /// waits for the child to exit. The stdin of the child gets closed first, so it doesn't
+    /// wait for input forever
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        drop(self.stdin.take());
+        return poll_fn(|cx| self.poll_wait(cx)).await;
+    }
+
+    /// returns the exit status if the child already exited, without waiting
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
+        self.status = self.child.try_wait()?;
+        return Ok(self.status);
+    }
+
+    pub fn poll_wait(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<ExitStatus>> {
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Poll::Ready(Ok(status));
+            }
+
+            match &self.reaper {
+                Reaper::Pidfd(pidfd) => {
+                    let mut guard = ready!(pidfd.poll_read_ready(cx))?;
+                    if let Some(status) = self.child.try_wait()? {
+                        self.status = Some(status);
+                        return Poll::Ready(Ok(status));
+                    }
+                    guard.clear_ready();
+                }
+                Reaper::Sigchld => {
+                    sigchld::register(cx.waker())?;
+                    if self.try_wait()?.is_none() {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+    }
+
+    /// sends `SIGKILL` to the child without waiting for it to exit
+    pub fn start_kill(&mut self) -> io::Result<()> {
+        if self.status.is_some() {
+            return Ok(());
+        }
+        return self.child.kill();
+    }
+
+    /// kills the child and waits for it to exit
+    pub async fn kill(&mut self) -> io::Result<()> {
+        self.start_kill()?;
+        self.wait().await?;
+        return Ok(());
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if self.status.is_some() {
+            return;
+        }
+        if self.kill_on_drop {
+            let _ = self.child.kill();
+        }
+        orphan::push(self.child.id() as libc::pid_t);
+    }
+}
+
+fn pidfd_open(pid: u32) -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    return Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) });
+}
+
+#[cfg(test)]
+mod test {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Command as StdCommand, Stdio};
+    use std::sync::{mpsc, Arc};
+    use std::task::{Wake, Waker};
+    use std::time::Duration;
+
+    use super::{sigchld, Child, Reaper};
+    use crate::io::{read::AsyncReadExt, write::AsyncWriteExt};
+    use crate::prelude::Runtime;
+    use crate::process::command::Command;
+
+    struct SignalWaker(mpsc::Sender<()>);
+
+    impl Wake for SignalWaker {
+        fn wake(self: Arc<Self>) {
+            let _ = self.0.send(());
+        }
+    }
+
+    /// blocks until `pid` is gone. Every `SIGCHLD` reaps the orphan queue before the waiters are
+    /// woken, so the pid is checked again after each one
+    fn wait_until_reaped(pid: libc::pid_t) {
+        let (tx, rx) = mpsc::channel();
+        let waker = Waker::from(Arc::new(SignalWaker(tx)));
+        loop {
+            sigchld::register(&waker).unwrap();
+            if unsafe { libc::kill(pid, 0) } < 0 {
+                return;
+            }
+            rx.recv_timeout(Duration::from_secs(5))
+                .expect("the child was never reaped");
+        }
+    }
+
+    #[test]
+    fn wait_for_exit_code() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+            assert!(matches!(child.reaper, Reaper::Pidfd(_)));
+            let status = child.wait().await.unwrap();
+            assert_eq!(status.code(), Some(3));
+            assert_eq!(child.id(), None);
+            assert_eq!(child.wait().await.unwrap(), status);
+        });
+    }
+
+    #[test]
+    fn pipe_through_cat() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut child = Command::new("cat")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+
+            let mut stdin = child.stdin.take().unwrap();
+            stdin.write(b"meow").await.unwrap();
+            drop(stdin);
+
+            let mut stdout = child.stdout.take().unwrap();
+            let mut buf = [0; 4];
+            let mut read = 0;
+            while read < 4 {
+                read += stdout.read(&mut buf[read..]).await.unwrap();
+            }
+            assert_eq!(&buf, b"meow");
+            assert!(child.wait().await.unwrap().success());
+        });
+    }
+
+    #[test]
+    fn kill() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+            child.kill().await.unwrap();
+            let status = child.try_wait().unwrap().unwrap();
+            assert_eq!(status.signal(), Some(libc::SIGKILL));
+        });
+    }
+
+    #[test]
+    fn kill_on_drop() {
+        let mut rt = Runtime::new();
+        let pid = rt.block_on(async {
+            let child = Command::new("sleep")
+                .arg("10")
+                .kill_on_drop(true)
+                .spawn()
+                .unwrap();
+            child.id().unwrap()
+        });
+
+        // the orphan queue reaps the killed child, afterwards the pid is gone
+        wait_until_reaped(pid as libc::pid_t);
+    }
+
+    #[test]
+    fn reap_dropped_child() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let child = Command::new("true").spawn().unwrap();
+            let pid = child.id().unwrap() as libc::pid_t;
+
+            // waits for the exit without reaping, so the child is a zombie when it gets dropped
+            let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+            let res = unsafe {
+                libc::waitid(
+                    libc::P_PID,
+                    pid as libc::id_t,
+                    &mut info,
+                    libc::WEXITED | libc::WNOWAIT,
+                )
+            };
+            assert_eq!(res, 0);
+            assert_eq!(unsafe { libc::kill(pid, 0) }, 0);
+
+            drop(child);
+            assert!(unsafe { libc::kill(pid, 0) } < 0);
+        });
+    }
+
+    #[test]
+    fn reap_dropped_running_child() {
+        let mut rt = Runtime::new();
+        let pid = rt.block_on(async {
+            let child = Command::new("sleep").arg("0.05").spawn().unwrap();
+            child.id().unwrap() as libc::pid_t
+        });
+
+        // the child is reaped once its `SIGCHLD` arrives, a zombie would still accept signals
+        wait_until_reaped(pid);
+    }
+
+    #[test]
+    fn sigchld_fallback() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let child = StdCommand::new("sh")
+                .args(["-c", "sleep 0.05; exit 7"])
+                .spawn()
+                .unwrap();
+            let mut child = Child::with_reaper(child, Reaper::Sigchld, false).unwrap();
+            assert_eq!(child.wait().await.unwrap().code(), Some(7));
+        });
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    ffi::OsStr,
+    future::poll_fn,
+    io,
+    path::Path,
+    pin::Pin,
+    process::{Command as StdCommand, ExitStatus, Output, Stdio},
+    task::{ready, Context, Poll},
+};
+
+use super::child::Child;
+use crate::io::{pipe::Receiver, read::AsyncRead};
+
+/// A builder for child processes, mirroring [std::process::Command]. The pipes of a spawned
+/// [Child] are registered with the reactor of the current runtime
+///
+/// ```
+/// use oxic::{prelude::Runtime, process::command::Command};
+///
+/// let mut rt = Runtime::new();
+/// rt.block_on(async {
+///     let output = Command::new("echo").arg("hello").output().await.unwrap();
+///     assert!(output.status.success());
+///     assert_eq!(output.stdout, b"hello\n");
+/// });
+/// ```
+pub struct Command {
+    inner: StdCommand,
+    kill_on_drop: bool,
+    stdin_set: bool,
+    stdout_set: bool,
+    stderr_set: bool,
+}
+
+impl Command {
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        return Self {
+            inner: StdCommand::new(program),
+            kill_on_drop: false,
+            stdin_set: false,
+            stdout_set: false,
+            stderr_set: false,
+        };
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.inner.arg(arg);
+        return self;
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        return self;
+    }
+
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.env(key, val);
+        return self;
+    }
+
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.envs(vars);
+        return self;
+    }
+
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.inner.env_remove(key);
+        return self;
+    }
+
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.inner.env_clear();
+        return self;
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.inner.current_dir(dir);
+        return self;
+    }
+
+    /// [Stdio::piped] makes [Child::stdin] available
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.inner.stdin(cfg);
+        self.stdin_set = true;
+        return self;
+    }
+
+    /// [Stdio::piped] makes [Child::stdout] available
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.inner.stdout(cfg);
+        self.stdout_set = true;
+        return self;
+    }
+
+    /// [Stdio::piped] makes [Child::stderr] available
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.inner.stderr(cfg);
+        self.stderr_set = true;
+        return self;
+    }
+
+    /// whether a [Child] that wasn't waited for gets killed when it is dropped, by default it
+    /// keeps running
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+        self.kill_on_drop = kill_on_drop;
+        return self;
+    }
+
+    /// spawns the child
+    ///
+    /// # Panics
+    /// panics when called outside of the context of a runtime, see
+    /// [Handle::current](crate::runtime::context::Handle::current)
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        return Child::new(self.inner.spawn()?, self.kill_on_drop);
+    }
+
+    /// spawns the child and waits for it to exit
+    pub async fn status(&mut self) -> io::Result<ExitStatus> {
+        return self.spawn()?.wait().await;
+    }
+
+    /// spawns the child, collects its stdout and stderr and waits for it to exit. Like for std,
+    /// the stdin of the child is empty and its stdout and stderr are piped unless they were set.
+    /// Those defaults only apply to this child, later spawns inherit the streams again
+    pub async fn output(&mut self) -> io::Result<Output> {
+        if !self.stdin_set {
+            self.inner.stdin(Stdio::null());
+        }
+        if !self.stdout_set {
+            self.inner.stdout(Stdio::piped());
+        }
+        if !self.stderr_set {
+            self.inner.stderr(Stdio::piped());
+        }
+        let child = self.spawn();
+        if !self.stdin_set {
+            self.inner.stdin(Stdio::inherit());
+        }
+        if !self.stdout_set {
+            self.inner.stdout(Stdio::inherit());
+        }
+        if !self.stderr_set {
+            self.inner.stderr(Stdio::inherit());
+        }
+
+        let mut child = child?;
+        let (mut stdout, mut stderr) = (child.stdout.take(), child.stderr.take());
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        // both have to be drained at once, the child might block on writing to the other one
+        poll_fn(|cx| {
+            let out_done = poll_read_to_end(cx, &mut stdout, &mut out)?;
+            let err_done = poll_read_to_end(cx, &mut stderr, &mut err)?;
+            if out_done.is_ready() && err_done.is_ready() {
+                return Poll::Ready(Ok::<_, io::Error>(()));
+            }
+            return Poll::Pending;
+        })
+        .await?;
+
+        return Ok(Output {
+            status: child.wait().await?,
+            stdout: out,
+            stderr: err,
+        });
+    }
+}
+
+impl From<StdCommand> for Command {
+    fn from(inner: StdCommand) -> Self {
+        return Self {
+            inner,
+            kill_on_drop: false,
+            stdin_set: false,
+            stdout_set: false,
+            stderr_set: false,
+        };
+    }
+}
+
+/// reads `pipe` until it is closed, the pipe is dropped afterwards
+fn poll_read_to_end(
+    cx: &mut Context<'_>,
+    pipe: &mut Option<Receiver>,
+    buf: &mut Vec<u8>,
+) -> Poll<io::Result<()>> {
+    let Some(receiver) = pipe else {
+        return Poll::Ready(Ok(()));
+    };
+
+    let mut chunk = [0u8; 4096];
+    loop {
+        match ready!(Pin::new(&mut *receiver).poll_read(cx, &mut chunk))? {
+            0 => {
+                *pipe = None;
+                return Poll::Ready(Ok(()));
+            }
+            n => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Command;
+    use crate::prelude::Runtime;
+
+    #[test]
+    fn output_of_both_streams() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            // more than a pipe buffer on stderr, while stdout is written after it
+            let output = Command::new("sh")
+                .args(["-c", "head -c 200000 /dev/zero >&2; echo done; exit 1"])
+                .output()
+                .await
+                .unwrap();
+            assert_eq!(output.status.code(), Some(1));
+            assert_eq!(output.stdout, b"done\n");
+            assert_eq!(output.stderr.len(), 200000);
+        });
+    }
+
+    #[test]
+    fn output_keeps_the_builder() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut command = Command::new("true");
+            assert!(command.output().await.unwrap().status.success());
+
+            let mut child = command.spawn().unwrap();
+            assert!(child.stdout.is_none());
+            assert!(child.stderr.is_none());
+            assert!(child.wait().await.unwrap().success());
+
+            // a stream that was set explicitly isn't replaced by a pipe
+            let output = Command::new("echo")
+                .arg("hello")
+                .stdout(std::process::Stdio::null())
+                .output()
+                .await
+                .unwrap();
+            assert!(output.stdout.is_empty());
+        });
+    }
+
+    #[test]
+    fn status() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let status = Command::new("true").status().await.unwrap();
+            assert!(status.success());
+        });
+    }
+}
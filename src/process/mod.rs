@@ -0,0 +1,4 @@
+pub mod child;
+pub mod command;
+pub(crate) mod orphan;
+pub(crate) mod sigchld;
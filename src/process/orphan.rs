@@ -0,0 +1,33 @@
+use std::{io, sync::Mutex};
+
+use super::sigchld;
+use crate::runtime::blocking::spawn_blocking;
+
+/// the children that got dropped before they were waited for
+static ORPHANS: Mutex<Vec<libc::pid_t>> = Mutex::new(Vec::new());
+
+/// queues `pid` to be reaped once it exited, so children that are dropped without being waited
+/// for don't stay around as zombies. The queue is checked whenever a `SIGCHLD` arrives
+pub(crate) fn push(pid: libc::pid_t) {
+    if sigchld::listen().is_err() {
+        // nothing tells when the child exited, a pool thread waits for it instead
+        drop(spawn_blocking(move || unsafe {
+            libc::waitpid(pid, std::ptr::null_mut(), 0)
+        }));
+        return;
+    }
+
+    ORPHANS.lock().unwrap().push(pid);
+    // the child might have exited before `SIGCHLD` was listened for
+    reap();
+}
+
+/// reaps every queued child that exited already
+pub(crate) fn reap() {
+    ORPHANS.lock().unwrap().retain(|&pid| {
+        let res = unsafe { libc::waitpid(pid, std::ptr::null_mut(), libc::WNOHANG) };
+        // a error other than an interrupt means the child is gone already
+        return res == 0
+            || (res < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted);
+    });
+}
@@ -0,0 +1,80 @@
+use std::{
+    io,
+    os::fd::AsRawFd,
+    sync::{Mutex, OnceLock},
+    task::Waker,
+    thread,
+};
+
+use super::orphan;
+use crate::signal::registry;
+
+/// the tasks waiting for any child to exit
+static WAITERS: Mutex<Vec<Waker>> = Mutex::new(Vec::new());
+/// the errno listening for `SIGCHLD` failed with, if it did
+static INIT: OnceLock<Option<i32>> = OnceLock::new();
+
+/// listens for `SIGCHLD` through the signalfd of the signal registry. A thread drains it, so
+/// waiting tasks get woken up even if no runtime polls a [Signal](crate::signal::Signal) for it,
+/// the registry wakes them whenever it reads a `SIGCHLD`
+fn init() -> Option<i32> {
+    let (slot, fd) = match registry::listen(libc::SIGCHLD) {
+        Ok(listener) => listener,
+        Err(e) => return Some(e.raw_os_error().unwrap_or(libc::EINVAL)),
+    };
+
+    thread::Builder::new()
+        .name("oxic-sigchld".into())
+        .spawn(move || {
+            let _slot = slot;
+            loop {
+                let mut pollfd = libc::pollfd {
+                    fd: fd.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                if unsafe { libc::poll(&mut pollfd, 1, -1) } < 0
+                    && io::Error::last_os_error().kind() != io::ErrorKind::Interrupted
+                {
+                    return;
+                }
+                if let Err(e) = registry::drain(&fd) {
+                    if e.kind() != io::ErrorKind::WouldBlock {
+                        return;
+                    }
+                }
+            }
+        })
+        .expect("failed to spawn the SIGCHLD thread");
+    return None;
+}
+
+/// wakes every task waiting for a child and reaps exited orphans, called whenever a `SIGCHLD`
+/// arrived
+pub(crate) fn wake_all() {
+    orphan::reap();
+    let waiters = std::mem::take(&mut *WAITERS.lock().unwrap());
+    for waker in waiters {
+        waker.wake();
+    }
+}
+
+/// starts listening for `SIGCHLD` unless it already happened
+pub(crate) fn listen() -> io::Result<()> {
+    if let Some(errno) = INIT.get_or_init(init) {
+        return Err(io::Error::from_raw_os_error(*errno));
+    }
+    return Ok(());
+}
+
+/// wakes `waker` once the next child exits. The caller has to check whether its child exited
+/// after registering, the signal might have arrived just before
+pub(crate) fn register(waker: &Waker) -> io::Result<()> {
+    listen()?;
+
+    let mut waiters = WAITERS.lock().unwrap();
+    if !waiters.iter().any(|waiter| waiter.will_wake(waker)) {
+        waiters.push(waker.clone());
+    }
+    return Ok(());
+}
@@ -0,0 +1,165 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use alloc::{boxed::Box, sync::Arc};
+
+/// the most threads the pool runs at once, further jobs wait for one of them
+const MAX_THREADS: usize = 64;
+/// how long a idle thread waits for a new job before it exits
+const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct State {
+    jobs: VecDeque<Job>,
+    threads: usize,
+    idle: usize,
+}
+
+/// A pool of threads for operations that block, so they don't stall the workers. It is shared
+/// by every runtime of the process, threads are spawned on demand and exit once they were idle
+/// for a while
+struct Pool {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+static POOL: Pool = Pool {
+    state: Mutex::new(State {
+        jobs: VecDeque::new(),
+        threads: 0,
+        idle: 0,
+    }),
+    condvar: Condvar::new(),
+};
+
+impl Pool {
+    fn execute(&'static self, job: Job) {
+        let mut state = self.state.lock().unwrap();
+        state.jobs.push_back(job);
+        if state.jobs.len() > state.idle && state.threads < MAX_THREADS {
+            state.threads += 1;
+            thread::Builder::new()
+                .name("oxic-blocking".into())
+                .spawn(move || self.run())
+                .expect("failed to spawn a blocking thread");
+        } else {
+            self.condvar.notify_one();
+        }
+    }
+
+    fn run(&'static self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                drop(state);
+                job();
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            state.idle += 1;
+            let (guard, timeout) = self.condvar.wait_timeout(state, KEEP_ALIVE).unwrap();
+            state = guard;
+            state.idle -= 1;
+            if timeout.timed_out() && state.jobs.is_empty() {
+                state.threads -= 1;
+                return;
+            }
+        }
+    }
+}
+
+struct Shared<R> {
+    result: Option<thread::Result<R>>,
+    waker: Option<Waker>,
+}
+
+/// runs `f` on the blocking pool, the returned future resolves to its result. A panic of `f`
+/// is resumed when the future is polled
+pub(crate) fn spawn_blocking<F, R>(f: F) -> BlockingTask<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let shared = Arc::new(Mutex::new(Shared {
+        result: None,
+        waker: None,
+    }));
+
+    let job_shared = shared.clone();
+    POOL.execute(Box::new(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        let waker = {
+            let mut shared = job_shared.lock().unwrap();
+            shared.result = Some(result);
+            shared.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }));
+
+    return BlockingTask { shared };
+}
+
+/// The result of a job running on the blocking pool, see [spawn_blocking]
+pub(crate) struct BlockingTask<R> {
+    shared: Arc<Mutex<Shared<R>>>,
+}
+
+impl<R> Future for BlockingTask<R> {
+    type Output = R;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap();
+        return match shared.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::spawn_blocking;
+    use crate::prelude::Runtime;
+
+    #[test]
+    fn run_on_pool() {
+        let mut rt = Runtime::new();
+        // every job waits for all the others, so they have to run on threads of their own
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(8));
+        let results = rt.block_on(async {
+            let tasks = (0..8)
+                .map(|i| {
+                    let barrier = barrier.clone();
+                    spawn_blocking(move || {
+                        barrier.wait();
+                        i * 2
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut results = Vec::new();
+            for task in tasks {
+                results.push(task.await);
+            }
+            results
+        });
+        assert_eq!(results, (0..8).map(|i| i * 2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,81 @@
+use core::{cell::RefCell, future::Future, marker::PhantomData};
+
+use alloc::sync::Arc;
+
+use crate::runtime::{executor::executor::Executor, handle::JoinHandle, reactor::reactor::Reactor};
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Handle>> = const { RefCell::new(None) };
+}
+
+/// A cloneable handle to a [Runtime](crate::runtime::runtime::Runtime).
+///
+/// Worker threads and threads inside of [Runtime::block_on](crate::runtime::runtime::Runtime::block_on)
+/// have the handle of their runtime set as the current one, I/O resources created there are
+/// registered with that runtime's [Reactor]
+#[derive(Clone)]
+pub struct Handle {
+    executor: Arc<Executor>,
+    reactor: Arc<Reactor>,
+}
+
+impl Handle {
+    pub(crate) fn new(executor: Arc<Executor>, reactor: Arc<Reactor>) -> Self {
+        return Self { executor, reactor };
+    }
+
+    /// returns the handle of the runtime the current thread belongs to
+    ///
+    /// # Panics
+    /// panics when called outside of the context of a runtime
+    pub fn current() -> Self {
+        return match Self::try_current() {
+            Some(handle) => handle,
+            None => panic!("must be called from the context of a oxic runtime"),
+        };
+    }
+
+    /// returns the handle of the runtime the current thread belongs to, if there is any
+    pub fn try_current() -> Option<Self> {
+        return CONTEXT.with(|ctx| ctx.borrow().clone());
+    }
+
+    /// makes this the current runtime of the calling thread until the returned guard is dropped
+    pub fn enter(&self) -> EnterGuard {
+        let previous = CONTEXT.with(|ctx| ctx.replace(Some(self.clone())));
+        return EnterGuard {
+            previous,
+            _not_send: PhantomData,
+        };
+    }
+
+    /// spawns a task onto the runtime, see [Runtime::spawn](crate::runtime::runtime::Runtime::spawn)
+    pub fn spawn<Fut, T>(&self, f: Fut) -> JoinHandle<T>
+    where
+        Fut: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        return self.executor.spawn(f);
+    }
+
+    pub(crate) fn executor(&self) -> &Arc<Executor> {
+        return &self.executor;
+    }
+
+    pub(crate) fn reactor(&self) -> &Arc<Reactor> {
+        return &self.reactor;
+    }
+}
+
+/// Restores the previously entered runtime once dropped, see [Handle::enter]
+pub struct EnterGuard {
+    previous: Option<Handle>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CONTEXT.with(|ctx| *ctx.borrow_mut() = previous);
+    }
+}
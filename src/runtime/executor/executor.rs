@@ -1,100 +1,295 @@
 use core::{
+    cell::RefCell,
     future::Future,
-    panic,
+    pin::pin,
+    sync::atomic::{AtomicBool, Ordering},
     task::{Context, Poll, Waker},
+    time::Duration,
+};
+use std::{
+    sync::{Condvar, Mutex},
+    time::Instant,
 };
-use std::sync::Mutex;
 
-use alloc::{collections::BTreeMap, sync::Arc};
+use alloc::{sync::Arc, vec::Vec};
 
-use crossbeam_queue::{ArrayQueue, SegQueue};
+use crossbeam_deque::{Injector, Steal, Stealer, Worker};
+use lockfree::map::Map;
 
 use crate::runtime::{
-    handle::JoinHandle,
+    executor::park::Parker,
+    handle::{self, JoinHandle},
+    reactor::reactor::Reactor,
     task::task::{Task, TaskId},
-    waker::TaskWaker,
+    waker::{BlockOnWaker, TaskWaker},
 };
 
-/// A Executor
+/// the number of spawned tasks a thread inside of [Executor::block_on] runs before it checks
+/// on the blocked on future again
+const BLOCK_ON_BUDGET: usize = 64;
+
+/// A work-stealing Executor.
+///
+/// Every worker thread owns a local queue, tasks spawned or woken from outside of a worker land
+/// in a global injector and idle workers steal from each other. Workers without anything to do
+/// are parked until new work gets scheduled, one of them waits for I/O events in the meantime
 pub struct Executor {
-    tasks: BTreeMap<TaskId, Task>,
-    pub(crate) task_queue: Arc<SegQueue<TaskId>>,
-    waker_cache: BTreeMap<TaskId, Waker>,
+    tasks: Map<TaskId, Arc<Task>>,
+    waker_cache: Map<TaskId, Waker>,
+    injector: Injector<Arc<Task>>,
+    stealers: Vec<Stealer<Arc<Task>>>,
+    parker: Arc<Parker>,
+    shutdown: AtomicBool,
+    active_workers: Mutex<usize>,
+    workers_done: Condvar,
 }
 
-//should be safe since it's always in a Arc<Mutex<T>> and there is only one thread polling it
-unsafe impl Send for Executor {}
+/// The state a worker thread keeps while running [run_executor]
+struct WorkerContext {
+    executor: *const Executor,
+    index: usize,
+    local: Worker<Arc<Task>>,
+}
+
+thread_local! {
+    static WORKER: RefCell<Option<WorkerContext>> = const { RefCell::new(None) };
+}
 
 impl Executor {
     pub fn new() -> Self {
-        return Self {
-            tasks: BTreeMap::new(),
-            task_queue: Arc::new(SegQueue::new()),
-            waker_cache: BTreeMap::new(),
+        return Self::with_workers(0, Arc::new(Reactor::new())).0;
+    }
+
+    /// creates a executor together with the local queues of its `num_workers` workers, each of
+    /// which has to be driven by [run_executor]. Workers that run out of tasks drive `reactor`
+    pub(crate) fn with_workers(
+        num_workers: usize,
+        reactor: Arc<Reactor>,
+    ) -> (Self, Vec<Worker<Arc<Task>>>) {
+        let workers: Vec<_> = (0..num_workers).map(|_| Worker::new_fifo()).collect();
+        let executor = Self {
+            tasks: Map::new(),
+            waker_cache: Map::new(),
+            injector: Injector::new(),
+            stealers: workers.iter().map(Worker::stealer).collect(),
+            parker: Arc::new(Parker::with_driver(reactor)),
+            shutdown: AtomicBool::new(false),
+            active_workers: Mutex::new(num_workers),
+            workers_done: Condvar::new(),
         };
+        return (executor, workers);
     }
 
-    /// spawns a [Task] onto the executor and polls it once. After that it will continue
-    /// execution asynchronously
-    pub fn spawn<Fut, T>(&mut self, f: Fut) -> JoinHandle<T>
+    /// spawns a [Task] onto the executor without polling it. It gets pushed onto the local queue
+    /// of the calling worker or the global injector and runs on whichever worker picks it up
+    pub fn spawn<Fut, T>(self: &Arc<Self>, f: Fut) -> JoinHandle<T>
     where
-        Fut: Future<Output = T> + 'static,
-        T: 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
     {
-        let result_reciever = Arc::new(ArrayQueue::new(1));
-        let result_sender = result_reciever.clone();
-        let fut = async move {
-            let v = f.await;
-            match result_sender.push(v) {
-                Ok(_) => (),
-                Err(_) => panic!("queue should never be full at this point"),
-            };
-        };
+        let id = TaskId::new();
+        let (fut, handle) = handle::harness(id, f);
 
-        let task = Task::new(fut);
-        let id = task.id;
-        if self.tasks.insert(id, task).is_some() {
+        let task = Arc::new(Task::with_id(id, fut));
+        if self.tasks.insert(id, task.clone()).is_some() {
             // this should never be reached since the TaskId is atomically incremented with each
             // call so getting the same twice here is impossible
             unreachable!();
         }
+        self.waker_cache
+            .insert(id, TaskWaker::new(task.clone(), Arc::downgrade(self)));
+
+        // nobody is going to run the task anymore, so its handle resolves to a cancelled error
+        if self.is_shutdown() {
+            self.remove_task(&task);
+            return handle;
+        }
+
+        self.schedule(task);
+        return handle;
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        return self.shutdown.load(Ordering::Acquire);
+    }
+
+    /// signals all workers to stop once they finished polling their current [Task]. Returns false
+    /// if the executor was already shut down
+    pub(crate) fn shutdown(&self) -> bool {
+        if self.shutdown.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+
+        self.parker.unpark_all();
+        return true;
+    }
+
+    /// waits until every worker stopped or the timeout elapsed, returns whether all of them
+    /// stopped
+    pub(crate) fn wait_for_workers(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut active = self.active_workers.lock().unwrap();
+        while *active != 0 {
+            active = match deadline {
+                None => self.workers_done.wait(active).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    self.workers_done
+                        .wait_timeout(active, deadline - now)
+                        .unwrap()
+                        .0
+                }
+            };
+        }
+        return true;
+    }
+
+    /// drops every remaining [Task], which resolves their handles to a cancelled error
+    pub(crate) fn drop_tasks(&self) {
+        let tasks: Vec<_> = self.tasks.iter().map(|guard| guard.val().clone()).collect();
+        for task in tasks {
+            self.remove_task(&task);
+        }
+    }
 
-        self.task_queue.push(id);
-        //self.task_queue.push(id);
-        return JoinHandle::new(result_reciever);
+    fn remove_task(&self, task: &Task) {
+        self.tasks.remove(&task.id);
+        self.waker_cache.remove(&task.id);
+        task.cancel();
     }
 
-    /// [spawn](Self::spawn<Fut,T>()) a [Task] in a blocking manner
-    pub fn block_on<Fut, T>(&mut self, f: Fut) -> T
+    /// drives the future to completion on the calling thread. An executor without workers of
+    /// its own also runs its spawned [Task]s and drives the reactor on the calling thread in the
+    /// meantime
+    pub fn block_on<Fut, T>(self: &Arc<Self>, f: Fut) -> T
     where
-        Fut: Future<Output = T> + 'static,
-        T: 'static,
+        Fut: Future<Output = T>,
     {
-        return self.spawn(f).join();
+        let current_thread = self.stealers.is_empty();
+        let parker = match current_thread {
+            true => self.parker.clone(),
+            false => Arc::new(Parker::new()),
+        };
+
+        let block_on_waker = Arc::new(BlockOnWaker::new(parker.clone()));
+        let waker = Waker::from(block_on_waker.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut f = pin!(f);
+
+        loop {
+            if block_on_waker.take_notified() {
+                if let Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+                    return v;
+                }
+            }
+
+            if current_thread {
+                for _ in 0..BLOCK_ON_BUDGET {
+                    match self.pop_injected() {
+                        Some(task) => self.run_task(task),
+                        None => break,
+                    }
+                }
+            }
+
+            parker.park(|| {
+                block_on_waker.is_notified() || (current_thread && !self.injector.is_empty())
+            });
+        }
+    }
+
+    /// takes the next [Task] out of the global injector
+    fn pop_injected(&self) -> Option<Arc<Task>> {
+        loop {
+            match self.injector.steal() {
+                Steal::Success(task) => return Some(task),
+                Steal::Retry => continue,
+                Steal::Empty => return None,
+            }
+        }
+    }
+
+    /// pushes a [Task] onto the local queue of the current worker, or onto the global injector
+    /// if the current thread is not one of this executor's workers. A parked worker is woken up
+    /// to either run or steal it
+    pub(crate) fn schedule(&self, task: Arc<Task>) {
+        let task = WORKER.with(|worker| match worker.borrow().as_ref() {
+            Some(ctx) if core::ptr::eq(ctx.executor, self) => {
+                ctx.local.push(task);
+                None
+            }
+            _ => Some(task),
+        });
+
+        if let Some(task) = task {
+            self.injector.push(task);
+        }
+        self.parker.unpark_one();
+    }
+
+    /// checks whether any queue still holds a runnable [Task]
+    fn has_work(&self) -> bool {
+        return !self.injector.is_empty() || self.stealers.iter().any(|s| !s.is_empty());
     }
 
-    pub(crate) fn run_task(&mut self, id: TaskId) {
-        let task = match self.tasks.get_mut(&id) {
-            Some(task) => task,
+    pub(crate) fn run_task(&self, task: Arc<Task>) {
+        let id = task.id;
+        let waker = match self.waker_cache.get(&id) {
+            Some(waker) => waker.val().clone(),
             None => {
                 return;
             }
         };
 
-        assert_eq!(task.id, id);
-
-        let waker = self
-            .waker_cache
-            .entry(id)
-            .or_insert_with(|| TaskWaker::new(id, self.task_queue.clone()));
-
-        let mut cx = Context::from_waker(waker);
+        let mut cx = Context::from_waker(&waker);
         match task.poll(&mut cx) {
             Poll::Ready(_) => {
                 self.tasks.remove(&id);
                 self.waker_cache.remove(&id);
             }
-            Poll::Pending => return,
+            // the runtime got shut down while the task was polled, so it couldn't be dropped
+            // back then
+            Poll::Pending if self.is_shutdown() => self.remove_task(&task),
+            Poll::Pending => {
+                if task.yielded() {
+                    self.schedule(task);
+                }
+            }
+        }
+    }
+
+    /// looks for the next [Task] to run, first in the local queue, then in the global injector
+    /// and finally in the queues of the other workers
+    fn find_task(&self, ctx: &WorkerContext) -> Option<Arc<Task>> {
+        if let Some(task) = ctx.local.pop() {
+            return Some(task);
+        }
+
+        loop {
+            let mut retry = false;
+
+            match self.injector.steal_batch_and_pop(&ctx.local) {
+                Steal::Success(task) => return Some(task),
+                Steal::Retry => retry = true,
+                Steal::Empty => (),
+            }
+
+            let num_stealers = self.stealers.len();
+            for i in 1..num_stealers {
+                let stealer = &self.stealers[(ctx.index + i) % num_stealers];
+                match stealer.steal_batch_and_pop(&ctx.local) {
+                    Steal::Success(task) => return Some(task),
+                    Steal::Retry => retry = true,
+                    Steal::Empty => (),
+                }
+            }
+
+            if !retry {
+                return None;
+            }
         }
     }
 }
@@ -106,29 +301,51 @@ impl Default for Executor {
 }
 
 #[inline(always)]
-pub(crate) fn run_executor(ex: Arc<Mutex<Executor>>) -> impl Fn() {
-    println!("running executor");
-    move || loop {
-        let mut ex = ex.lock().unwrap();
-        if let Some(id) = ex.task_queue.pop() {
-            ex.run_task(id);
+pub(crate) fn run_executor(
+    ex: Arc<Executor>,
+    index: usize,
+    local: Worker<Arc<Task>>,
+) -> impl FnOnce() {
+    move || {
+        WORKER.with(|worker| {
+            *worker.borrow_mut() = Some(WorkerContext {
+                executor: Arc::as_ptr(&ex),
+                index,
+                local,
+            });
+        });
+
+        while !ex.is_shutdown() {
+            let task = WORKER.with(|worker| {
+                let worker = worker.borrow();
+                let ctx = worker.as_ref().expect("worker context should be set");
+                ex.find_task(ctx)
+            });
+
+            match task {
+                Some(task) => ex.run_task(task),
+                None => ex.parker.park(|| ex.has_work() || ex.is_shutdown()),
+            }
         }
+
+        WORKER.with(|worker| *worker.borrow_mut() = None);
+        let mut active = ex.active_workers.lock().unwrap();
+        *active -= 1;
+        ex.workers_done.notify_all();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Mutex;
+    use core::sync::atomic::{AtomicUsize, Ordering};
 
-    use alloc::{string::String, sync::Arc};
+    use alloc::{string::String, sync::Arc, vec::Vec};
 
     use crossbeam_queue::SegQueue;
 
-    use crate::{prelude::Runtime, runtime::executor::executor::run_executor};
+    use crate::{prelude::Runtime, runtime::runtime::RuntimeBuilder, test_util::YieldNow};
 
-    use super::Executor;
-
-    //#[test]
+    #[test]
     fn run() {
         let q = Arc::new(SegQueue::new());
         let q1 = q.clone();
@@ -141,18 +358,18 @@ mod tests {
         assert_eq!(q.pop().unwrap(), "Hello");
     }
 
-    //#[test]
+    #[test]
     fn with_return() {
         async fn hello() -> String {
             return String::from("Hello");
         }
 
-        let mut ex = Executor::new();
-        let res = ex.block_on(hello());
+        let mut rt = Runtime::new();
+        let res = rt.block_on(hello());
         assert_eq!(res, "Hello");
     }
 
-    //#[test]
+    #[test]
     fn nested() {
         async fn bottom() -> u32 {
             7
@@ -165,8 +382,68 @@ mod tests {
             middle().await
         }
 
-        let mut ex = Executor::new();
-        let res = ex.block_on(top());
+        let mut rt = Runtime::new();
+        let res = rt.block_on(top());
         assert_eq!(res, 7);
     }
+
+    #[test]
+    fn work_stealing() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut rt = RuntimeBuilder::new().threads(4).build();
+        let handles: Vec<_> = (0..64)
+            .map(|i| {
+                let counter = counter.clone();
+                rt.spawn(async move {
+                    YieldNow(i % 8).await;
+                    counter.fetch_add(1, Ordering::Relaxed);
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.load(Ordering::Relaxed), 64);
+    }
+
+    #[test]
+    fn wake_parked_worker() {
+        struct WaitForFlag(
+            Arc<std::sync::Mutex<(bool, Option<core::task::Waker>)>>,
+            std::sync::mpsc::Sender<()>,
+        );
+        impl core::future::Future for WaitForFlag {
+            type Output = ();
+            fn poll(
+                self: core::pin::Pin<&mut Self>,
+                cx: &mut core::task::Context<'_>,
+            ) -> core::task::Poll<()> {
+                let mut state = self.0.lock().unwrap();
+                if state.0 {
+                    return core::task::Poll::Ready(());
+                }
+                state.1 = Some(cx.waker().clone());
+                let _ = self.1.send(());
+                return core::task::Poll::Pending;
+            }
+        }
+
+        let state = Arc::new(std::sync::Mutex::new((false, None)));
+        let mut rt = RuntimeBuilder::new().threads(2).build();
+        let (polled, waiting) = std::sync::mpsc::channel();
+        let handle = rt.spawn(WaitForFlag(state.clone(), polled));
+
+        // the worker parks once the task is waiting for the flag
+        waiting.recv().unwrap();
+        let waker = {
+            let mut state = state.lock().unwrap();
+            state.0 = true;
+            state.1.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        handle.join().unwrap();
+    }
 }
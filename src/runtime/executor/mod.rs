@@ -1 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod executor;
+pub(crate) mod park;
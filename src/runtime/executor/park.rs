@@ -0,0 +1,102 @@
+use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+
+use alloc::sync::Arc;
+
+use crate::runtime::reactor::reactor::Reactor;
+
+/// Puts idle threads to sleep until new work is scheduled.
+///
+/// A parker created with a driver lets the first idle thread block on the [Reactor] instead of
+/// the condvar, so I/O events get dispatched by the thread that is going to run the woken tasks
+pub(crate) struct Parker {
+    lock: Mutex<()>,
+    condvar: Condvar,
+    sleepers: AtomicUsize,
+    driver: Option<Arc<Reactor>>,
+    /// whether a thread is currently driving the reactor
+    driving: AtomicBool,
+}
+
+impl Parker {
+    pub(crate) fn new() -> Self {
+        return Self {
+            lock: Mutex::new(()),
+            condvar: Condvar::new(),
+            sleepers: AtomicUsize::new(0),
+            driver: None,
+            driving: AtomicBool::new(false),
+        };
+    }
+
+    /// creates a parker whose idle threads take turns at driving `reactor`
+    pub(crate) fn with_driver(reactor: Arc<Reactor>) -> Self {
+        return Self {
+            driver: Some(reactor),
+            ..Self::new()
+        };
+    }
+
+    /// parks the calling thread unless `has_work` reports that something got scheduled in the
+    /// meantime. `has_work` is checked after the thread announced itself as sleeping so a
+    /// concurrent [Parker::unpark_one] can't get lost
+    pub(crate) fn park(&self, has_work: impl Fn() -> bool) {
+        // a reactor that shut down returns from every turn right away, the thread would spin
+        if let Some(reactor) = self
+            .driver
+            .as_ref()
+            .filter(|reactor| !reactor.is_shutdown())
+        {
+            if self
+                .driving
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                fence(Ordering::SeqCst);
+                if !has_work() {
+                    reactor.turn(None);
+                }
+                self.driving.store(false, Ordering::SeqCst);
+                return;
+            }
+        }
+
+        let guard = self.lock.lock().unwrap();
+        self.sleepers.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+
+        if !has_work() {
+            let _guard = self.condvar.wait(guard).unwrap();
+        }
+
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// wakes up every parked thread
+    pub(crate) fn unpark_all(&self) {
+        {
+            let _guard = self.lock.lock().unwrap();
+            self.condvar.notify_all();
+        }
+        if let Some(reactor) = &self.driver {
+            reactor.unpark();
+        }
+    }
+
+    /// wakes up a single parked thread, should be called after new work got pushed. Threads
+    /// sleeping on the condvar are preferred over interrupting the one driving the reactor
+    pub(crate) fn unpark_one(&self) {
+        fence(Ordering::SeqCst);
+        if self.sleepers.load(Ordering::SeqCst) != 0 {
+            let _guard = self.lock.lock().unwrap();
+            self.condvar.notify_one();
+            return;
+        }
+
+        if let Some(reactor) = &self.driver {
+            if self.driving.load(Ordering::SeqCst) {
+                reactor.unpark();
+            }
+        }
+    }
+}
@@ -1,43 +1,436 @@
 use core::{
+    any::Any,
+    fmt,
     future::Future,
     marker::PhantomData,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
 };
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Condvar, Mutex,
+    },
+};
+
+use alloc::{boxed::Box, string::String, sync::Arc};
+
+use crate::runtime::task::task::TaskId;
+
+enum Repr {
+    Cancelled,
+    Panic(Mutex<Box<dyn Any + Send + 'static>>),
+}
+
+/// The reason a [Task](crate::task::Task) didn't produce a result
+pub struct JoinError {
+    id: TaskId,
+    repr: Repr,
+}
+
+impl JoinError {
+    pub(crate) fn cancelled(id: TaskId) -> Self {
+        return Self {
+            id,
+            repr: Repr::Cancelled,
+        };
+    }
+
+    pub(crate) fn panic(id: TaskId, payload: Box<dyn Any + Send + 'static>) -> Self {
+        return Self {
+            id,
+            repr: Repr::Panic(Mutex::new(payload)),
+        };
+    }
+
+    /// the id of the task that failed
+    pub fn id(&self) -> TaskId {
+        return self.id;
+    }
+
+    /// whether the task got cancelled before it completed
+    pub fn is_cancelled(&self) -> bool {
+        return matches!(self.repr, Repr::Cancelled);
+    }
+
+    /// whether the task panicked
+    pub fn is_panic(&self) -> bool {
+        return matches!(self.repr, Repr::Panic(_));
+    }
+
+    /// returns the payload the task panicked with, so that it can be resumed with
+    /// [std::panic::resume_unwind]
+    ///
+    /// # Panics
+    /// panics if the task didn't panic but got cancelled
+    pub fn into_panic(self) -> Box<dyn Any + Send + 'static> {
+        return match self.try_into_panic() {
+            Ok(payload) => payload,
+            Err(e) => panic!("`JoinError` reason is not a panic: {e}"),
+        };
+    }
+
+    /// returns the payload the task panicked with or the error itself if it got cancelled
+    pub fn try_into_panic(self) -> Result<Box<dyn Any + Send + 'static>, JoinError> {
+        return match self.repr {
+            Repr::Panic(payload) => Ok(payload.into_inner().unwrap()),
+            Repr::Cancelled => Err(self),
+        };
+    }
+
+    fn panic_message(&self) -> Option<String> {
+        let Repr::Panic(payload) = &self.repr else {
+            return None;
+        };
+
+        let payload = payload.lock().unwrap();
+        if let Some(s) = payload.downcast_ref::<&str>() {
+            return Some(String::from(*s));
+        }
+        return payload.downcast_ref::<String>().cloned();
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match (&self.repr, self.panic_message()) {
+            (Repr::Cancelled, _) => write!(f, "task {:?} was cancelled", self.id),
+            (Repr::Panic(_), Some(msg)) => write!(f, "task {:?} panicked with `{msg}`", self.id),
+            (Repr::Panic(_), None) => write!(f, "task {:?} panicked", self.id),
+        };
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match &self.repr {
+            Repr::Cancelled => write!(f, "JoinError::Cancelled({:?})", self.id),
+            Repr::Panic(_) => write!(
+                f,
+                "JoinError::Panic({:?}, {:?})",
+                self.id,
+                self.panic_message()
+            ),
+        };
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+struct JoinInner<T> {
+    result: Option<Result<T, JoinError>>,
+    waker: Option<Waker>,
+}
+
+/// The shared slot a [Task](crate::task::Task) stores its result in once it completed
+pub(crate) struct JoinState<T> {
+    inner: Mutex<JoinInner<T>>,
+    completed: Condvar,
+}
+
+impl<T> JoinState<T> {
+    pub(crate) fn new() -> Self {
+        return Self {
+            inner: Mutex::new(JoinInner {
+                result: None,
+                waker: None,
+            }),
+            completed: Condvar::new(),
+        };
+    }
+
+    /// stores the result, wakes up every thread blocked in [JoinHandle::join] and the task
+    /// awaiting the [JoinHandle]
+    pub(crate) fn complete(&self, value: Result<T, JoinError>) {
+        let waker = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.result = Some(value);
+            self.completed.notify_all();
+            inner.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Completes the [JoinState] of a task. If it gets dropped before a result was sent, e.g. because
+/// the task itself got dropped, the [JoinHandle] resolves to a cancelled [JoinError]
+struct ResultSender<T> {
+    id: TaskId,
+    state: Option<Arc<JoinState<T>>>,
+}
+
+impl<T> ResultSender<T> {
+    fn send(mut self, result: Result<T, JoinError>) {
+        if let Some(state) = self.state.take() {
+            state.complete(result);
+        }
+    }
+}
+
+impl<T> Drop for ResultSender<T> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            state.complete(Err(JoinError::cancelled(self.id)));
+        }
+    }
+}
+
+/// Polls the inner future and turns a panic into an error instead of unwinding further
+struct CatchUnwind<Fut>(Fut);
+
+impl<Fut: Future> Future for CatchUnwind<Fut> {
+    type Output = Result<Fut::Output, Box<dyn Any + Send + 'static>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the inner future is never moved out of the pinned wrapper
+        let fut = unsafe { self.map_unchecked_mut(|s| &mut s.0) };
+        return match panic::catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+            Ok(Poll::Ready(v)) => Poll::Ready(Ok(v)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        };
+    }
+}
+
+/// The abort flag of a task together with the waker needed to schedule it once more, so that it
+/// notices the flag
+struct AbortState {
+    aborted: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl AbortState {
+    fn abort(&self) {
+        self.aborted.store(true, Ordering::SeqCst);
+        let waker = self.waker.lock().unwrap().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
 
-use alloc::sync::Arc;
-use crossbeam_queue::ArrayQueue;
+/// Resolves to [None] instead of polling the inner future once the task got aborted
+struct Abortable<Fut> {
+    future: Fut,
+    state: Arc<AbortState>,
+}
+
+impl<Fut: Future> Future for Abortable<Fut> {
+    type Output = Option<Fut::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        {
+            // the waker is registered before the flag is checked, so that a concurrent abort
+            // either sees the waker or gets noticed right here
+            let mut waker = self.state.waker.lock().unwrap();
+            match waker.as_ref() {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => *waker = Some(cx.waker().clone()),
+            }
+        }
+
+        if self.state.aborted.load(Ordering::SeqCst) {
+            return Poll::Ready(None);
+        }
 
-/// An owned permission to wait until a [Task](crate::task::Task) is driven to completion
+        // SAFETY: the inner future is never moved out of the pinned wrapper
+        let fut = unsafe { self.map_unchecked_mut(|s| &mut s.future) };
+        return fut.poll(cx).map(Some);
+    }
+}
+
+/// wraps `f` into the future that is actually run as a [Task](crate::task::Task). Its result,
+/// the panic it raised or the fact that it got aborted or dropped early ends up in the returned
+/// [JoinHandle]
+pub(crate) fn harness<Fut, T>(id: TaskId, f: Fut) -> (impl Future<Output = ()>, JoinHandle<T>)
+where
+    Fut: Future<Output = T>,
+{
+    let join_state = Arc::new(JoinState::new());
+    let abort_state = Arc::new(AbortState {
+        aborted: AtomicBool::new(false),
+        waker: Mutex::new(None),
+    });
+    let result_sender = ResultSender {
+        id,
+        state: Some(join_state.clone()),
+    };
+
+    let abortable = Abortable {
+        future: CatchUnwind(f),
+        state: abort_state.clone(),
+    };
+    let fut = async move {
+        // the aborted future is already dropped once the sender reports the cancellation
+        if let Some(result) = abortable.await {
+            result_sender.send(result.map_err(|payload| JoinError::panic(id, payload)));
+        }
+    };
+
+    let abort_handle = AbortHandle {
+        id,
+        state: abort_state,
+    };
+    return (fut, JoinHandle::new(join_state, abort_handle));
+}
+
+/// An owned permission to abort a [Task](crate::task::Task) without awaiting it
+#[derive(Clone)]
+pub struct AbortHandle {
+    id: TaskId,
+    state: Arc<AbortState>,
+}
+
+impl AbortHandle {
+    /// aborts the task. Its future is dropped the next time it gets scheduled and the
+    /// [JoinHandle] resolves to a cancelled [JoinError], unless the task already completed
+    pub fn abort(&self) {
+        self.state.abort();
+    }
+
+    /// the id of the task this handle aborts
+    pub fn id(&self) -> TaskId {
+        return self.id;
+    }
+}
+
+impl fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "AbortHandle({:?})", self.id);
+    }
+}
+
+/// An owned permission to wait until a [Task](crate::task::Task) is driven to completion.
+///
+/// Dropping it detaches the task, use [JoinHandle::abort] to cancel it
 pub struct JoinHandle<T> {
-    rx: Arc<ArrayQueue<T>>,
+    state: Arc<JoinState<T>>,
+    abort_handle: AbortHandle,
     _phantom_data: PhantomData<fn() -> T>,
 }
 
 impl<T> JoinHandle<T> {
-    pub fn new(rx: Arc<ArrayQueue<T>>) -> Self {
+    fn new(state: Arc<JoinState<T>>, abort_handle: AbortHandle) -> Self {
         Self {
-            rx,
+            state,
+            abort_handle,
             _phantom_data: PhantomData,
         }
     }
 
-    /// waits for the [Task](crate::task::Task) to be driven to completion and returns its result
-    pub fn join(&self) -> T {
+    /// see [AbortHandle::abort]
+    pub fn abort(&self) {
+        self.abort_handle.abort();
+    }
+
+    /// returns a cloneable handle which can abort the task
+    pub fn abort_handle(&self) -> AbortHandle {
+        return self.abort_handle.clone();
+    }
+
+    /// waits for the [Task](crate::task::Task) to be driven to completion and returns its result.
+    /// The calling thread is parked until the result arrives, inside of a task the [JoinHandle]
+    /// should be awaited instead
+    pub fn join(&self) -> Result<T, JoinError> {
+        let mut inner = self.state.inner.lock().unwrap();
         loop {
-            match self.rx.pop() {
+            match inner.result.take() {
                 Some(x) => return x,
-                None => continue,
+                None => inner = self.state.completed.wait(inner).unwrap(),
             }
         }
     }
 }
 
-/// see [JoinHandle::join](struct.JoinHandle.html#method.join)
+/// Resolves to the result of the [Task](crate::task::Task) once it completed. Until then the
+/// waker of the awaiting task is stored and woken on completion
 impl<T> Future for JoinHandle<T> {
-    type Output = T;
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.state.inner.lock().unwrap();
+        if let Some(x) = inner.result.take() {
+            return Poll::Ready(x);
+        }
+
+        match inner.waker.as_ref() {
+            Some(waker) if waker.will_wake(cx.waker()) => (),
+            _ => inner.waker = Some(cx.waker().clone()),
+        }
+        return Poll::Pending;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::runtime::RuntimeBuilder;
+    use crate::test_util::YieldNow;
+
+    #[test]
+    fn await_on_single_worker() {
+        let mut rt = RuntimeBuilder::new().threads(1).build();
+        let inner = rt.spawn(async {
+            YieldNow(10).await;
+            return 7;
+        });
+        let outer = rt.spawn(async move { inner.await.unwrap() + 1 });
+        assert_eq!(outer.join().unwrap(), 8);
+    }
+
+    #[test]
+    fn await_on_current_thread() {
+        let mut rt = RuntimeBuilder::current_thread().build();
+        let inner = rt.spawn(async {
+            YieldNow(10).await;
+            return 7;
+        });
+        let outer = rt.spawn(async move { inner.await.unwrap() + 1 });
+        assert_eq!(rt.block_on(outer).unwrap(), 8);
+    }
+
+    #[test]
+    fn abort() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        use alloc::sync::Arc;
+
+        struct SetOnDrop(Arc<AtomicBool>);
+        impl Drop for SetOnDrop {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let guard = SetOnDrop(dropped.clone());
+
+        let mut rt = RuntimeBuilder::new().threads(2).build();
+        let handle = rt.spawn(async move {
+            let _guard = guard;
+            core::future::pending::<()>().await;
+        });
+        let abort_handle = handle.abort_handle();
+        abort_handle.clone().abort();
+
+        let err = handle.join().unwrap_err();
+        assert!(err.is_cancelled());
+        assert_eq!(err.id(), abort_handle.id());
+        assert!(dropped.load(Ordering::SeqCst));
+    }
 
-    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
-        return Poll::Ready(self.join());
+    #[test]
+    fn abort_completed() {
+        let mut rt = RuntimeBuilder::current_thread().build();
+        let handle = rt.spawn(async { 3 });
+        let res = rt.block_on(async move {
+            YieldNow(1).await;
+            handle.abort();
+            handle.await
+        });
+        assert_eq!(res.unwrap(), 3);
     }
 }
@@ -1,6 +1,9 @@
+pub(crate) mod blocking;
+pub mod context;
 pub mod executor;
 pub mod handle;
 pub mod reactor;
+#[allow(clippy::module_inception)]
 pub mod runtime;
 pub mod task;
 pub mod waker;
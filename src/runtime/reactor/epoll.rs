@@ -2,38 +2,55 @@ use std::{io, os::fd::RawFd};
 
 use epoll::{ControlOptions, Event};
 
-pub struct Epoll(pub RawFd);
+pub struct Epoll {
+    fd: RawFd,
+}
+
+//read flags =  Events::EPOLLET | Events::EPOLLIN;
+//write flags =  Events::EPOLLET | Events::EPOLLOUT;
 
-//read flags =  Events::EPOLLONESHOT | Events::EPOLLIN;
-//write flags =  Events::EPOLLONESHOT | Events::EPOLLOUT;
+impl Default for Epoll {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
 
 impl Epoll {
     pub fn new() -> Self {
-        return Self(epoll::create(true).expect("can't create a epoll queue"));
+        return Self {
+            fd: epoll::create(true).expect("can't create a epoll queue"),
+        };
     }
 
     pub fn add_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
-        return epoll::ctl(self.0, ControlOptions::EPOLL_CTL_ADD, fd, event);
+        return epoll::ctl(self.fd, ControlOptions::EPOLL_CTL_ADD, fd, event);
     }
 
     pub fn modify_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
-        return epoll::ctl(self.0, ControlOptions::EPOLL_CTL_MOD, fd, event);
+        return epoll::ctl(self.fd, ControlOptions::EPOLL_CTL_MOD, fd, event);
     }
 
     pub fn remove_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
-        return epoll::ctl(self.0, ControlOptions::EPOLL_CTL_DEL, fd, event);
+        return epoll::ctl(self.fd, ControlOptions::EPOLL_CTL_DEL, fd, event);
     }
 
+    /// waits for events and writes them into `events`, returns how many were written
     pub fn wait(&self, timeout: Option<u32>, events: &mut [Event]) -> io::Result<usize> {
         let timeout = match timeout {
             None => -1,
-            Some(x) => x as i32,
+            Some(x) => x.min(i32::MAX as u32) as i32,
         };
 
-        return epoll::wait(self.0, timeout, events);
+        return epoll::wait(self.fd, timeout, events);
     }
 
     pub fn close(&self) -> io::Result<()> {
-        return epoll::close(self.0);
+        return epoll::close(self.fd);
+    }
+}
+
+impl Drop for Epoll {
+    fn drop(&mut self) {
+        let _ = self.close();
     }
 }
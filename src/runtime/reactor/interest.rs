@@ -1,5 +1,3 @@
-use std::os::fd::RawFd;
-
 use epoll::Events;
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -25,39 +23,40 @@ impl From<epoll::Events> for Interest {
     }
 }
 
+/// A interest in or the readiness of the fd registered under `token`
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Event {
-    pub fd: RawFd,
+    pub token: usize,
     pub interest: Interest,
 }
 
 impl Event {
-    pub fn new(fd: RawFd, interest: Interest) -> Self {
-        Self { fd, interest }
+    pub fn new(token: usize, interest: Interest) -> Self {
+        Self { token, interest }
     }
 }
 
-impl Into<epoll::Event> for Event {
-    fn into(self) -> epoll::Event {
-        return epoll::Event::new(self.interest.into(), self.fd as u64);
+impl From<Event> for epoll::Event {
+    fn from(value: Event) -> Self {
+        return epoll::Event::new(value.interest.into(), value.token as u64);
     }
 }
 
 impl From<&epoll::Event> for Event {
     fn from(value: &epoll::Event) -> Self {
         Self {
-            fd: value.data as i32,
+            token: value.data as usize,
             interest: Interest::from(epoll::Events::from_bits_truncate(value.events)),
         }
     }
 }
 
-impl Into<Events> for Interest {
-    fn into(self) -> Events {
-        return match self {
-            Interest::Read => Events::EPOLLONESHOT | Events::EPOLLIN,
-            Interest::Write => Events::EPOLLONESHOT | Events::EPOLLOUT,
-            Interest::ReadWrite => Events::EPOLLONESHOT | Events::EPOLLOUT | Events::EPOLLIN,
+impl From<Interest> for Events {
+    fn from(value: Interest) -> Self {
+        return match value {
+            Interest::Read => Events::EPOLLET | Events::EPOLLIN,
+            Interest::Write => Events::EPOLLET | Events::EPOLLOUT,
+            Interest::ReadWrite => Events::EPOLLET | Events::EPOLLOUT | Events::EPOLLIN,
         };
     }
 }
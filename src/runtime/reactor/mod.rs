@@ -1,4 +1,11 @@
 pub mod epoll;
-pub mod reactor;
 pub mod interest;
+pub mod poll;
 pub mod poller;
+#[allow(clippy::module_inception)]
+pub mod reactor;
+pub(crate) mod registration;
+pub(crate) mod scheduled_io;
+pub(crate) mod slab;
+#[cfg(feature = "io-uring")]
+pub mod uring;
@@ -0,0 +1,231 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    os::fd::RawFd,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use super::{
+    interest::{Event, Interest},
+    scheduled_io::{readiness_of, READABLE, WRITABLE},
+};
+
+/// A fd registered with [Poll]
+struct Entry {
+    token: usize,
+    interest: Interest,
+    /// the directions the fd is polled for, a direction gets disarmed once it was reported
+    armed: usize,
+}
+
+/// A poller built on `poll(2)`.
+///
+/// `poll` is level-triggered, so to behave like the edge-triggered [Epoll](super::epoll::Epoll)
+/// a direction is only reported once until it gets rearmed through [Poll::rearm]. The set of
+/// polled fds is rebuilt on every wait, a self-pipe interrupts a wait when it changes
+pub struct Poll {
+    entries: Mutex<BTreeMap<RawFd, Entry>>,
+    /// the read and write end of the self-pipe
+    pipe: [RawFd; 2],
+    /// whether a thread is blocked in [Poll::wait]
+    polling: AtomicBool,
+}
+
+impl Default for Poll {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Poll {
+    pub fn new() -> Self {
+        let mut pipe = [0; 2];
+        if unsafe { libc::pipe2(pipe.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } < 0 {
+            panic!("can't create a pipe: {}", io::Error::last_os_error());
+        }
+
+        return Self {
+            entries: Mutex::new(BTreeMap::new()),
+            pipe,
+            polling: AtomicBool::new(false),
+        };
+    }
+
+    pub fn add_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if entries.contains_key(&fd) {
+                return Err(io::Error::from_raw_os_error(libc::EEXIST));
+            }
+            entries.insert(
+                fd,
+                Entry {
+                    token: event.token,
+                    interest: event.interest,
+                    armed: readiness_of(event.interest),
+                },
+            );
+        }
+        self.notify();
+        return Ok(());
+    }
+
+    pub fn modify_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries
+                .get_mut(&fd)
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::ENOENT))?;
+            entry.token = event.token;
+            entry.interest = event.interest;
+            entry.armed = readiness_of(event.interest);
+        }
+        self.notify();
+        return Ok(());
+    }
+
+    pub fn remove_interest(&self, fd: RawFd) -> io::Result<()> {
+        return match self.entries.lock().unwrap().remove(&fd) {
+            Some(_) => Ok(()),
+            None => Err(io::Error::from_raw_os_error(libc::ENOENT)),
+        };
+    }
+
+    /// polls the fd for `interest` again after it was reported
+    pub fn rearm(&self, fd: RawFd, interest: Interest) -> io::Result<()> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries
+                .get_mut(&fd)
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::ENOENT))?;
+            let rearmed = readiness_of(interest) & readiness_of(entry.interest);
+            if entry.armed & rearmed == rearmed {
+                return Ok(());
+            }
+            entry.armed |= rearmed;
+        }
+        self.notify();
+        return Ok(());
+    }
+
+    pub fn wait(&self, timeout: Option<u32>, events: &mut [epoll::Event]) -> io::Result<usize> {
+        self.polling.store(true, Ordering::SeqCst);
+        let mut fds = vec![libc::pollfd {
+            fd: self.pipe[0],
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        fds.extend(
+            self.entries
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, entry)| entry.armed != 0)
+                .map(|(fd, entry)| libc::pollfd {
+                    fd: *fd,
+                    events: poll_events(entry.armed),
+                    revents: 0,
+                }),
+        );
+
+        let timeout = match timeout {
+            None => -1,
+            Some(x) => x.min(i32::MAX as u32) as i32,
+        };
+        let res = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) };
+        self.polling.store(false, Ordering::SeqCst);
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if fds[0].revents != 0 {
+            self.drain();
+        }
+
+        let mut n = 0;
+        let mut entries = self.entries.lock().unwrap();
+        for pollfd in &fds[1..] {
+            if n == events.len() {
+                break;
+            }
+            if pollfd.revents == 0 {
+                continue;
+            }
+            // the fd might have been removed or rearmed while polling
+            let Some(entry) = entries.get_mut(&pollfd.fd) else {
+                continue;
+            };
+
+            let readiness = readiness_of_revents(pollfd.revents) & entry.armed;
+            if readiness == 0 {
+                continue;
+            }
+            entry.armed &= !readiness;
+            events[n] = Event::new(entry.token, interest_of(readiness)).into();
+            n += 1;
+        }
+        return Ok(n);
+    }
+
+    /// interrupts a thread blocked in [Poll::wait], so it picks up the changed fds
+    fn notify(&self) {
+        if !self.polling.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let byte: u8 = 1;
+        unsafe { libc::write(self.pipe[1], &byte as *const u8 as *const libc::c_void, 1) };
+    }
+
+    fn drain(&self) {
+        let mut buf = [0u8; 64];
+        while unsafe { libc::read(self.pipe[0], buf.as_mut_ptr() as *mut libc::c_void, 64) } > 0 {}
+    }
+}
+
+impl Drop for Poll {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.pipe[0]);
+            libc::close(self.pipe[1]);
+        }
+    }
+}
+
+fn poll_events(readiness: usize) -> libc::c_short {
+    let mut events = 0;
+    if readiness & READABLE != 0 {
+        events |= libc::POLLIN;
+    }
+    if readiness & WRITABLE != 0 {
+        events |= libc::POLLOUT;
+    }
+    return events;
+}
+
+fn readiness_of_revents(revents: libc::c_short) -> usize {
+    // errors and hangups are reported to both directions, the next operation surfaces them
+    if revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0 {
+        return READABLE | WRITABLE;
+    }
+
+    let mut readiness = 0;
+    if revents & libc::POLLIN != 0 {
+        readiness |= READABLE;
+    }
+    if revents & libc::POLLOUT != 0 {
+        readiness |= WRITABLE;
+    }
+    return readiness;
+}
+
+fn interest_of(readiness: usize) -> Interest {
+    return match readiness {
+        READABLE => Interest::Read,
+        WRITABLE => Interest::Write,
+        _ => Interest::ReadWrite,
+    };
+}
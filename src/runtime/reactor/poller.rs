@@ -1,47 +1,103 @@
-use std::io;
+use std::{io, os::fd::RawFd};
 
 use super::epoll::Epoll;
-use super::interest::Event;
+use super::interest::{Event, Interest};
+use super::poll::Poll;
+#[cfg(feature = "io-uring")]
+use super::uring::Uring;
+
+/// Selects the system interface a [Poller] waits for readiness with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Epoll,
+    /// `poll(2)`, for environments where epoll is not available
+    Poll,
+    /// epoll for readiness together with a io_uring for completion-based operations
+    #[cfg(feature = "io-uring")]
+    IoUring,
+}
 
 pub enum Poller {
     Epoll(Epoll),
+    Poll(Poll),
+    #[cfg(feature = "io-uring")]
+    IoUring(Box<Uring>),
+}
+
+impl Default for Poller {
+    fn default() -> Self {
+        return Self::new();
+    }
 }
 
 impl Poller {
     pub fn new() -> Self {
-        let poller = Epoll::new();
-        return Self::Epoll(poller);
+        return Self::with_backend(Backend::default());
+    }
+
+    pub fn with_backend(backend: Backend) -> Self {
+        return match backend {
+            Backend::Epoll => Self::Epoll(Epoll::new()),
+            Backend::Poll => Self::Poll(Poll::new()),
+            #[cfg(feature = "io-uring")]
+            Backend::IoUring => Self::IoUring(Box::default()),
+        };
     }
 
-    pub fn add_interest(&self, event: Event) -> Result<(), std::io::Error> {
+    pub fn backend(&self) -> Backend {
+        return match self {
+            Poller::Epoll(_) => Backend::Epoll,
+            Poller::Poll(_) => Backend::Poll,
+            #[cfg(feature = "io-uring")]
+            Poller::IoUring(_) => Backend::IoUring,
+        };
+    }
+
+    pub fn add_interest(&self, fd: RawFd, event: Event) -> Result<(), std::io::Error> {
         match self {
-            Poller::Epoll(epoll) => epoll.add_interest(event.fd, event.into()),
+            Poller::Epoll(epoll) => epoll.add_interest(fd, event.into()),
+            Poller::Poll(poll) => poll.add_interest(fd, event),
+            #[cfg(feature = "io-uring")]
+            Poller::IoUring(uring) => uring.add_interest(fd, event),
         }
     }
-    pub fn remove_interest(&self, event: Event) -> Result<(), std::io::Error> {
+    pub fn remove_interest(&self, fd: RawFd, event: Event) -> Result<(), std::io::Error> {
         match self {
-            Poller::Epoll(epoll) => epoll.remove_interest(event.fd, event.into()),
+            Poller::Epoll(epoll) => epoll.remove_interest(fd, event.into()),
+            Poller::Poll(poll) => poll.remove_interest(fd),
+            #[cfg(feature = "io-uring")]
+            Poller::IoUring(uring) => uring.remove_interest(fd, event),
         }
     }
-    pub fn modify_interest(&self, event: Event) -> Result<(), std::io::Error> {
+    pub fn modify_interest(&self, fd: RawFd, event: Event) -> Result<(), std::io::Error> {
+        match self {
+            Poller::Epoll(epoll) => epoll.modify_interest(fd, event.into()),
+            Poller::Poll(poll) => poll.modify_interest(fd, event),
+            #[cfg(feature = "io-uring")]
+            Poller::IoUring(uring) => uring.modify_interest(fd, event),
+        }
+    }
+
+    /// has to be called once a task waits for `interest` after it got reported for the fd.
+    /// Edge-triggered backends report every new edge on their own
+    pub fn rearm(&self, fd: RawFd, interest: Interest) -> Result<(), std::io::Error> {
         match self {
-            Poller::Epoll(epoll) => epoll.modify_interest(event.fd, event.into()),
+            Poller::Epoll(_) => Ok(()),
+            Poller::Poll(poll) => poll.rearm(fd, interest),
+            #[cfg(feature = "io-uring")]
+            Poller::IoUring(_) => Ok(()),
         }
     }
 
-    pub fn wait(
-        &self,
-        timeout: Option<u32>,
-        events: &mut [Event],
-    ) -> io::Result<(usize, Vec<Event>)> {
+    /// waits for events and writes them into `events`, returns how many were written. Every
+    /// backend reports in the epoll representation, so the buffer is shared by all of them
+    pub fn wait(&self, timeout: Option<u32>, events: &mut [epoll::Event]) -> io::Result<usize> {
         match self {
-            Poller::Epoll(epoll) => {
-                let mut epoll_buf = Vec::with_capacity(events.len());
-                epoll_buf.resize(events.len(), epoll::Event::new(epoll::Events::empty(), 0));
-                let size = epoll.wait(timeout, &mut epoll_buf)?;
-                let res: Vec<_> = epoll_buf.iter().map(Event::from).collect();
-                return Ok((size, res));
-            }
+            Poller::Epoll(epoll) => return epoll.wait(timeout, events),
+            Poller::Poll(poll) => return poll.wait(timeout, events),
+            #[cfg(feature = "io-uring")]
+            Poller::IoUring(uring) => return uring.wait(timeout, events),
         };
     }
 }
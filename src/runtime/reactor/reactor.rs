@@ -1,142 +1,526 @@
-use std::{os::fd::RawFd, sync::OnceLock, task::Waker, thread};
+use std::{
+    io,
+    os::fd::RawFd,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
 
 use super::{
     interest::{Event, Interest},
-    poller::Poller,
+    poller::{Backend, Poller},
+    scheduled_io::{readiness_of, ReadyEvent, ScheduledIo, Waiters, READABLE, WRITABLE},
+    slab::Slab,
 };
-use lockfree::map::Map;
 
-#[derive(Debug, Clone)]
-struct Subscription {
-    waker: Waker,
-    interest: Interest,
-}
-
-impl Subscription {
-    fn new(waker: Waker, interest: Interest) -> Self {
-        Self { waker, interest }
-    }
-}
+/// the token the eventfd used for [Reactor::unpark] is registered with
+const WAKE_TOKEN: usize = usize::MAX;
 
+/// Waits for I/O readiness and wakes the tasks waiting on it.
+///
+/// Every [Runtime](crate::runtime::runtime::Runtime) owns its own reactor. It has no thread of
+/// its own, instead idle workers call [Reactor::turn] and run the tasks it wakes up themselves.
+/// A fd is registered once and edge-triggered, after that it is addressed by the token it got
+/// registered with
 pub struct Reactor {
-    subscribtions: Map<i32, Subscription>,
+    registrations: Mutex<Slab<Arc<ScheduledIo>>>,
     poller: Poller,
+    events: Mutex<Vec<epoll::Event>>,
+    /// a eventfd used to interrupt [Reactor::turn] when new work got scheduled
+    wake_fd: RawFd,
+    /// whether the eventfd got written to since the last turn
+    woken: AtomicBool,
+    shutdown: AtomicBool,
+}
+
+impl Default for Reactor {
+    fn default() -> Self {
+        return Self::new();
+    }
 }
 
 impl Reactor {
     pub fn new() -> Reactor {
+        return Self::with_backend(Backend::default());
+    }
+
+    /// creates a reactor waiting for readiness through `backend`
+    pub fn with_backend(backend: Backend) -> Reactor {
+        let wake_fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if wake_fd < 0 {
+            panic!("can't create a eventfd: {}", io::Error::last_os_error());
+        }
+
+        let poller = Poller::with_backend(backend);
+        poller
+            .add_interest(wake_fd, Event::new(WAKE_TOKEN, Interest::Read))
+            .expect("failed to add the eventfd to the epoll queue");
+
         return Self {
-            subscribtions: Map::new(),
-            poller: Poller::new(),
+            registrations: Mutex::new(Slab::new()),
+            poller,
+            events: Mutex::new(vec![epoll::Event::new(epoll::Events::empty(), 0); 1024]),
+            wake_fd,
+            woken: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
         };
     }
 
-    pub fn get() -> &'static Reactor {
-        static REACTOR: OnceLock<Reactor> = OnceLock::new();
-        return REACTOR.get_or_init(|| {
-            thread::spawn(Self::reactor_loop);
-            Self::new()
-        });
+    /// adds `fd` to the poller and returns the token addressing it from now on
+    pub(crate) fn register(
+        &self,
+        fd: RawFd,
+        interest: Interest,
+    ) -> io::Result<(usize, Arc<ScheduledIo>)> {
+        if self.is_shutdown() {
+            return Err(gone());
+        }
+
+        let io = Arc::new(ScheduledIo::new(fd));
+        let token = self.registrations.lock().unwrap().insert(io.clone());
+        if let Err(e) = self.poller.add_interest(fd, Event::new(token, interest)) {
+            self.registrations.lock().unwrap().remove(token);
+            return Err(e);
+        }
+        return Ok((token, io));
     }
 
-    pub fn register(&self, fd: RawFd, waker: Waker, interest: Interest) {
-        // we need to clone this subsription here otherwise we get problem registering the fd for
-        // some weird reason, cannot be bothered to find why it actually fails
-        let sub = Subscription::new(waker.clone(), interest);
-        let _ = self.subscribtions.insert(fd, sub);
-        let event = Event::new(fd, interest);
-        self.poller
-            .add_interest(event)
-            .expect("failed to add interest to epoll queue");
+    /// removes the fd registered under `token` from the poller, has to be called before the fd
+    /// gets closed
+    pub(crate) fn deregister(&self, token: usize) -> io::Result<()> {
+        let Some(io) = self.registrations.lock().unwrap().remove(token) else {
+            return Ok(());
+        };
+        return self
+            .poller
+            .remove_interest(io.fd, Event::new(token, Interest::default()));
+    }
+
+    /// checks whether the fd is ready for `interest`. If it isn't the task is stored in the
+    /// reader or writer slot until the next event for the fd arrives. Fails once the reactor
+    /// shut down, since no event is going to arrive anymore
+    pub(crate) fn poll_ready(
+        &self,
+        io: &ScheduledIo,
+        cx: &mut Context<'_>,
+        interest: Interest,
+    ) -> Poll<io::Result<ReadyEvent>> {
+        let readiness = readiness_of(interest);
+        if let Some(event) = io.ready_event(readiness) {
+            return Poll::Ready(Ok(event));
+        }
+
+        let mut waiters = io.waiters.lock().unwrap();
+        // the fd might have become ready while the lock was taken, events dispatched after
+        // this point find the task in its slot. The same goes for a shutdown
+        if let Some(event) = io.ready_event(readiness) {
+            return Poll::Ready(Ok(event));
+        }
+        if self.is_shutdown() {
+            return Poll::Ready(Err(gone()));
+        }
+        if readiness & READABLE != 0 {
+            waiters.reader = Some(cx.waker().clone());
+        }
+        if readiness & WRITABLE != 0 {
+            waiters.writer = Some(cx.waker().clone());
+        }
+        if let Err(e) = self.poller.rearm(io.fd, interest) {
+            return Poll::Ready(Err(e));
+        }
+        return Poll::Pending;
     }
 
-    pub fn remove(&self, fd: RawFd) {
-        let rem = self
-            .subscribtions
-            .remove(&fd)
-            .expect("failed to remove from epoll queue");
-        self.poller
-            .remove_interest(Event::new(fd, rem.1.interest))
-            .expect("failed to remove from epoll queue");
+    /// waits at most `timeout` for I/O events and wakes the tasks waiting on them. Returns
+    /// early once [Reactor::unpark] is called.
+    ///
+    /// If waiting fails the reactor can't be used anymore, it shuts down so the tasks waiting on
+    /// it get a error instead of hanging
+    pub fn turn(&self, timeout: Option<Duration>) {
+        let mut buf = self.events.lock().unwrap();
+        let timeout = timeout.map(|timeout| timeout.as_millis().min(u32::MAX as u128) as u32);
+        let n = loop {
+            match self.poller.wait(timeout, &mut buf) {
+                Ok(n) => break n,
+                // a signal arrived while waiting
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    drop(buf);
+                    self.shutdown();
+                    return;
+                }
+            }
+        };
+
+        for event in buf[..n].iter().map(Event::from) {
+            if event.token == WAKE_TOKEN {
+                self.clear_wake_fd();
+                continue;
+            }
+
+            let Some(io) = self.registrations.lock().unwrap().get(event.token).cloned() else {
+                continue;
+            };
+            self.dispatch(&io, readiness_of(event.interest));
+        }
     }
 
-    fn wait(&self, mut buf: &mut Vec<Event>) {
-        let n = self
-            .poller
-            .wait(None, &mut buf)
-            .expect("failed to wait for events");
+    /// stores the readiness reported for a fd and wakes the tasks waiting for it
+    fn dispatch(&self, io: &ScheduledIo, readiness: usize) {
+        io.set_readiness(readiness);
 
-        for i in 0..n.0 {
-            let event = n.1[i];
-            let fd = event.fd;
-            let sub = self
-                .subscribtions
-                .remove(&fd)
-                .expect("subscription should exist");
-            self.poller
-                .remove_interest(Event::new(fd, sub.1.interest))
-                .expect("failed to remove from epoll queue");
-            println!("notifing waker");
-            sub.1.waker.wake_by_ref();
+        let (reader, writer) = {
+            let mut waiters = io.waiters.lock().unwrap();
+            let reader = match readiness & READABLE != 0 {
+                true => waiters.reader.take(),
+                false => None,
+            };
+            let writer = match readiness & WRITABLE != 0 {
+                true => waiters.writer.take(),
+                false => None,
+            };
+            (reader, writer)
+        };
+
+        if let Some(reader) = reader {
+            reader.wake();
+        }
+        if let Some(writer) = writer {
+            writer.wake();
         }
     }
 
-    pub fn run(&self) {
-        let mut buf = Vec::with_capacity(1024);
-        buf.resize(1024, Event::default());
-        dbg!(buf.len());
-        loop {
-            self.wait(&mut buf);
+    /// interrupts the thread currently blocked in [Reactor::turn], if there is any
+    pub fn unpark(&self) {
+        if self.woken.swap(true, Ordering::AcqRel) {
+            return;
         }
+
+        let one: u64 = 1;
+        unsafe {
+            libc::write(
+                self.wake_fd,
+                &one as *const u64 as *const libc::c_void,
+                core::mem::size_of::<u64>(),
+            )
+        };
+    }
+
+    /// drains the eventfd, so the next [Reactor::unpark] interrupts a wait again
+    fn clear_wake_fd(&self) {
+        let mut value: u64 = 0;
+        unsafe {
+            libc::read(
+                self.wake_fd,
+                &mut value as *mut u64 as *mut libc::c_void,
+                core::mem::size_of::<u64>(),
+            )
+        };
+        self.woken.store(false, Ordering::Release);
+        let _ = self.poller.rearm(self.wake_fd, Interest::Read);
+    }
+
+    /// the io_uring driver, if the reactor was created with [Backend::IoUring]
+    #[cfg(feature = "io-uring")]
+    pub fn uring(&self) -> Option<&super::uring::Uring> {
+        return match &self.poller {
+            Poller::IoUring(uring) => Some(uring),
+            _ => None,
+        };
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        return self.shutdown.load(Ordering::Acquire);
     }
 
-    pub fn reactor_loop() {
-        let reactor = Self::get();
-        let mut buf = Vec::with_capacity(1024);
-        buf.resize(1024, Event::default());
-        loop {
-            reactor.wait(&mut buf);
+    /// marks the reactor as shut down, interrupts the thread driving it and wakes every task
+    /// waiting on I/O, which then fails with a error
+    pub fn shutdown(&self) {
+        if self.shutdown.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.unpark();
+
+        let registrations: Vec<_> = self.registrations.lock().unwrap().iter().cloned().collect();
+        for io in registrations {
+            let Waiters { reader, writer } = core::mem::take(&mut *io.waiters.lock().unwrap());
+            if let Some(reader) = reader {
+                reader.wake();
+            }
+            if let Some(writer) = writer {
+                writer.wake();
+            }
+        }
+        #[cfg(feature = "io-uring")]
+        if let Some(uring) = self.uring() {
+            uring.wake_all();
         }
     }
 }
 
+/// the error I/O resources fail with once their reactor is gone
+pub(crate) fn gone() -> io::Error {
+    return io::Error::other("the reactor has shut down");
+}
+
+impl Drop for Reactor {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.wake_fd) };
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use std::{
         net::UdpSocket,
-        os::fd::{AsRawFd, RawFd},
-        sync::Arc,
-        task::{Wake, Waker},
+        os::fd::AsRawFd,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::{Context, Poll, Wake, Waker},
+        time::{Duration, Instant},
+    };
+
+    use crate::runtime::reactor::{
+        interest::Interest,
+        scheduled_io::{ScheduledIo, READABLE, WRITABLE},
     };
 
-    use crate::runtime::reactor::interest::Interest;
+    use super::{Backend, Reactor};
+
+    /// runs every reactor test once per [Backend]
+    macro_rules! backend_tests {
+        ($($name:ident),* $(,)?) => {
+            mod epoll {
+                $(
+                    #[test]
+                    fn $name() {
+                        super::$name(super::Backend::Epoll);
+                    }
+                )*
+            }
+
+            mod poll {
+                $(
+                    #[test]
+                    fn $name() {
+                        super::$name(super::Backend::Poll);
+                    }
+                )*
+            }
+        };
+    }
+
+    backend_tests!(
+        register_fd,
+        register_twice,
+        turn_wakes_subscriber,
+        separate_reader_and_writer,
+        shutdown_fails_pending,
+        unpark_interrupts_turn,
+        register_while_turning,
+    );
+
+    fn bind() -> UdpSocket {
+        return UdpSocket::bind("127.0.0.1:0").unwrap();
+    }
+
+    fn register_fd(backend: Backend) {
+        let reactor = Reactor::with_backend(backend);
+
+        let socket = bind();
+        let fd = socket.as_raw_fd();
+        let (token, _) = reactor.register(fd, Interest::Read).unwrap();
+        reactor.deregister(token).unwrap();
+        assert!(reactor.registrations.lock().unwrap().is_empty());
+    }
 
-    use super::Reactor;
+    fn register_twice(backend: Backend) {
+        let reactor = Reactor::with_backend(backend);
+
+        let socket = bind();
+        let fd = socket.as_raw_fd();
+        let (token, _) = reactor.register(fd, Interest::Read).unwrap();
+        let err = reactor
+            .register(fd, Interest::Read)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+        assert_eq!(reactor.registrations.lock().unwrap().len(), 1);
+        reactor.deregister(token).unwrap();
+    }
+
+    struct FlagWaker(AtomicBool);
+    impl FlagWaker {
+        fn new() -> Arc<Self> {
+            return Arc::new(Self(AtomicBool::new(false)));
+        }
 
-    struct TestWaker;
-    impl Wake for TestWaker {
+        fn take(&self) -> bool {
+            return self.0.swap(false, Ordering::SeqCst);
+        }
+    }
+    impl Wake for FlagWaker {
         fn wake(self: Arc<Self>) {
-            println!("woken up")
+            self.0.store(true, Ordering::SeqCst);
         }
+    }
+
+    /// clears the readiness a fresh registration starts with, like a operation returning
+    /// WouldBlock would
+    fn would_block(io: &ScheduledIo, readiness: usize) {
+        io.clear_readiness(io.ready_event(readiness).unwrap());
+    }
+
+    fn turn_wakes_subscriber(backend: Backend) {
+        let reactor = Reactor::with_backend(backend);
 
-        fn wake_by_ref(self: &Arc<Self>) {
-            println!("woken up")
+        let socket = bind();
+        let (_, io) = reactor
+            .register(socket.as_raw_fd(), Interest::Read)
+            .unwrap();
+        would_block(&io, READABLE);
+
+        let flag = FlagWaker::new();
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        assert!(reactor
+            .poll_ready(&io, &mut cx, Interest::Read)
+            .is_pending());
+
+        reactor.turn(Some(Duration::ZERO));
+        assert!(!flag.take());
+
+        socket
+            .send_to(b"hello", socket.local_addr().unwrap())
+            .unwrap();
+        reactor.turn(Some(Duration::from_secs(5)));
+        assert!(flag.take());
+
+        // the readiness stays cached until it gets cleared
+        assert!(reactor.poll_ready(&io, &mut cx, Interest::Read).is_ready());
+        reactor.turn(Some(Duration::ZERO));
+        assert!(reactor.poll_ready(&io, &mut cx, Interest::Read).is_ready());
+    }
+
+    fn separate_reader_and_writer(backend: Backend) {
+        let reactor = Reactor::with_backend(backend);
+
+        let socket = bind();
+        let (_, io) = reactor
+            .register(socket.as_raw_fd(), Interest::ReadWrite)
+            .unwrap();
+        would_block(&io, READABLE | WRITABLE);
+
+        let reader = FlagWaker::new();
+        let reader_waker = Waker::from(reader.clone());
+        let writer = FlagWaker::new();
+        let writer_waker = Waker::from(writer.clone());
+
+        let mut cx = Context::from_waker(&reader_waker);
+        assert!(reactor
+            .poll_ready(&io, &mut cx, Interest::Read)
+            .is_pending());
+        let mut cx = Context::from_waker(&writer_waker);
+        assert!(reactor
+            .poll_ready(&io, &mut cx, Interest::Write)
+            .is_pending());
+
+        // the socket is writable right away, the reader stays registered
+        reactor.turn(Some(Duration::from_secs(5)));
+        assert!(writer.take());
+        assert!(!reader.take());
+
+        socket
+            .send_to(b"hello", socket.local_addr().unwrap())
+            .unwrap();
+        reactor.turn(Some(Duration::from_secs(5)));
+        assert!(reader.take());
+        assert!(!writer.take());
+    }
+
+    fn shutdown_fails_pending(backend: Backend) {
+        let reactor = Reactor::with_backend(backend);
+
+        let socket = bind();
+        let (_, io) = reactor
+            .register(socket.as_raw_fd(), Interest::Read)
+            .unwrap();
+        would_block(&io, READABLE);
+
+        let flag = FlagWaker::new();
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        assert!(reactor
+            .poll_ready(&io, &mut cx, Interest::Read)
+            .is_pending());
+
+        reactor.shutdown();
+        assert!(flag.take());
+        match reactor.poll_ready(&io, &mut cx, Interest::Read) {
+            Poll::Ready(Err(_)) => (),
+            _ => panic!("expected the shut down reactor to fail"),
         }
+
+        let other = bind();
+        assert!(reactor.register(other.as_raw_fd(), Interest::Read).is_err());
     }
 
-    #[test]
-    pub fn register_fd() {
-        let reactor = Reactor::new();
+    fn unpark_interrupts_turn(backend: Backend) {
+        let reactor = Arc::new(Reactor::with_backend(backend));
+        let start = Instant::now();
 
-        let socket = UdpSocket::bind("127.0.0.1:3006").unwrap();
-        let fd = socket.as_raw_fd();
-        reactor.register(fd, Waker::from(Arc::new(TestWaker {})), Interest::Read);
+        let r = reactor.clone();
+        let (start_turn, turning) = std::sync::mpsc::channel();
+        let unparker = std::thread::spawn(move || {
+            turning.recv().unwrap();
+            r.unpark();
+        });
+        // the unpark lands while the turn waits or right before it, either way it ends the turn
+        start_turn.send(()).unwrap();
+        reactor.turn(Some(Duration::from_secs(5)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        unparker.join().unwrap();
+
+        // the eventfd has to be usable for the next unpark
+        reactor.unpark();
+        reactor.turn(Some(Duration::from_secs(5)));
+        assert!(start.elapsed() < Duration::from_secs(5));
     }
 
-    pub fn run_reactor() {
-        let reactor = Reactor::new();
+    fn register_while_turning(backend: Backend) {
+        let reactor = Arc::new(Reactor::with_backend(backend));
+        let flag = FlagWaker::new();
 
-        let socket = UdpSocket::bind("127.0.0.1:3006").unwrap();
-        let fd = socket.as_raw_fd();
-        reactor.register(fd, Waker::from(Arc::new(TestWaker {})), Interest::Read);
+        let r = reactor.clone();
+        let f = flag.clone();
+        let (start_turn, turning) = std::sync::mpsc::channel();
+        let registrar = std::thread::spawn(move || {
+            turning.recv().unwrap();
+            let socket = bind();
+            let (_, io) = r.register(socket.as_raw_fd(), Interest::Read).unwrap();
+            would_block(&io, READABLE);
+
+            let waker = Waker::from(f);
+            let mut cx = Context::from_waker(&waker);
+            assert!(r.poll_ready(&io, &mut cx, Interest::Read).is_pending());
+            socket
+                .send_to(b"hello", socket.local_addr().unwrap())
+                .unwrap();
+            return (socket, io);
+        });
+
+        // the fd got registered after the wait started, it still has to be picked up
+        start_turn.send(()).unwrap();
+        let start = Instant::now();
+        while !flag.take() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            reactor.turn(Some(Duration::from_secs(5)));
+        }
+        let _ = registrar.join().unwrap();
     }
 }
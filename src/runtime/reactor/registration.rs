@@ -0,0 +1,83 @@
+use std::{
+    io,
+    os::fd::RawFd,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use crate::runtime::context::Handle;
+
+use super::{
+    interest::Interest,
+    reactor::Reactor,
+    scheduled_io::{ReadyEvent, ScheduledIo},
+};
+
+/// A fd registered with a [Reactor] for as long as this lives, the I/O resources keep one of
+/// these next to their fd and have to drop it before the fd gets closed
+pub(crate) struct Registration {
+    reactor: Arc<Reactor>,
+    token: usize,
+    io: Arc<ScheduledIo>,
+}
+
+impl Registration {
+    /// registers `fd` with the reactor of the current runtime
+    ///
+    /// # Panics
+    /// panics when called outside of the context of a runtime, see [Handle::current]
+    pub(crate) fn new(fd: RawFd, interest: Interest) -> io::Result<Self> {
+        return Self::with_reactor(Handle::current().reactor().clone(), fd, interest);
+    }
+
+    pub(crate) fn with_reactor(
+        reactor: Arc<Reactor>,
+        fd: RawFd,
+        interest: Interest,
+    ) -> io::Result<Self> {
+        let (token, io) = reactor.register(fd, interest)?;
+        return Ok(Self { reactor, token, io });
+    }
+
+    #[cfg(feature = "io-uring")]
+    pub(crate) fn reactor(&self) -> &Arc<Reactor> {
+        return &self.reactor;
+    }
+
+    /// resolves once the fd is ready for `interest`
+    pub(crate) fn poll_ready(
+        &self,
+        cx: &mut Context<'_>,
+        interest: Interest,
+    ) -> Poll<io::Result<ReadyEvent>> {
+        return self.reactor.poll_ready(&self.io, cx, interest);
+    }
+
+    /// forgets the readiness of `event`, should be called once a operation would block
+    pub(crate) fn clear_readiness(&self, event: ReadyEvent) {
+        self.io.clear_readiness(event);
+    }
+
+    /// runs the nonblocking operation `f` until it doesn't return
+    /// [WouldBlock](io::ErrorKind::WouldBlock) anymore, waiting for `interest` in between
+    pub(crate) fn poll_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        interest: Interest,
+        mut f: impl FnMut() -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        loop {
+            let event = ready!(self.poll_ready(cx, interest))?;
+            match f() {
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => self.clear_readiness(event),
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let _ = self.reactor.deregister(self.token);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    os::fd::RawFd,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    task::Waker,
+};
+
+use super::interest::Interest;
+
+pub(crate) const READABLE: usize = 0b01;
+pub(crate) const WRITABLE: usize = 0b10;
+
+/// the readiness bits are stored in the lower half of [ScheduledIo::readiness], the upper half
+/// counts the events dispatched for the fd
+const TICK_SHIFT: u32 = usize::BITS / 2;
+const READINESS_MASK: usize = (1 << TICK_SHIFT) - 1;
+
+/// the readiness bits a [Interest] is made of
+pub(crate) fn readiness_of(interest: Interest) -> usize {
+    return match interest {
+        Interest::Read => READABLE,
+        Interest::Write => WRITABLE,
+        Interest::ReadWrite => READABLE | WRITABLE,
+    };
+}
+
+/// The tasks waiting for a fd to become ready, one slot per direction
+#[derive(Default)]
+pub(crate) struct Waiters {
+    pub(crate) reader: Option<Waker>,
+    pub(crate) writer: Option<Waker>,
+}
+
+/// The readiness a [ScheduledIo] reported together with the tick it was reported at
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadyEvent {
+    tick: usize,
+    pub(crate) readiness: usize,
+}
+
+/// The state the reactor keeps for every registered fd: the readiness it got reported and the
+/// tasks waiting on it. It is stored in the reactor's slab under the token the fd got registered
+/// with.
+///
+/// Fds are registered edge-triggered, so the readiness is cached until a operation returns
+/// [WouldBlock](std::io::ErrorKind::WouldBlock). A fresh fd is assumed to be ready, the first
+/// operation finds out whether it actually is
+pub struct ScheduledIo {
+    pub(crate) fd: RawFd,
+    readiness: AtomicUsize,
+    pub(crate) waiters: Mutex<Waiters>,
+}
+
+impl ScheduledIo {
+    pub(crate) fn new(fd: RawFd) -> Self {
+        return Self {
+            fd,
+            readiness: AtomicUsize::new(READABLE | WRITABLE),
+            waiters: Mutex::new(Waiters::default()),
+        };
+    }
+
+    /// returns the cached readiness out of `readiness` if there is any
+    pub(crate) fn ready_event(&self, readiness: usize) -> Option<ReadyEvent> {
+        let current = self.readiness.load(Ordering::Acquire);
+        let ready = current & readiness;
+        if ready == 0 {
+            return None;
+        }
+
+        return Some(ReadyEvent {
+            tick: current >> TICK_SHIFT,
+            readiness: ready,
+        });
+    }
+
+    /// adds the readiness of a new event
+    pub(crate) fn set_readiness(&self, readiness: usize) {
+        let _ = self
+            .readiness
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                let tick = (current >> TICK_SHIFT).wrapping_add(1);
+                Some((tick << TICK_SHIFT) | (current & READINESS_MASK) | readiness)
+            });
+    }
+
+    /// clears the readiness of `event` once a operation would block. Does nothing if a new
+    /// event got dispatched since `event` was returned, since the fd might be ready again
+    pub(crate) fn clear_readiness(&self, event: ReadyEvent) {
+        let _ = self
+            .readiness
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                if current >> TICK_SHIFT != event.tick {
+                    return None;
+                }
+                Some(current & !event.readiness)
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScheduledIo, READABLE, WRITABLE};
+
+    #[test]
+    fn clear_readiness() {
+        let io = ScheduledIo::new(-1);
+        let event = io.ready_event(READABLE).unwrap();
+        assert_eq!(event.readiness, READABLE);
+
+        io.clear_readiness(event);
+        assert!(io.ready_event(READABLE).is_none());
+        assert!(io.ready_event(WRITABLE).is_some());
+    }
+
+    #[test]
+    fn keep_newer_readiness() {
+        let io = ScheduledIo::new(-1);
+        let event = io.ready_event(READABLE).unwrap();
+
+        // a event dispatched after the operation would have blocked must not get lost
+        io.set_readiness(READABLE);
+        io.clear_readiness(event);
+        assert!(io.ready_event(READABLE).is_some());
+    }
+}
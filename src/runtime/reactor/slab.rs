@@ -0,0 +1,96 @@
+use alloc::vec::Vec;
+
+/// A vector whose slots are addressed by a token and get reused once they are removed
+pub struct Slab<T> {
+    entries: Vec<Option<T>>,
+    free: Vec<usize>,
+}
+
+impl<T> Slab<T> {
+    pub fn new() -> Self {
+        return Self {
+            entries: Vec::new(),
+            free: Vec::new(),
+        };
+    }
+
+    /// stores `value` in a free slot and returns the token addressing it
+    pub fn insert(&mut self, value: T) -> usize {
+        return match self.free.pop() {
+            Some(token) => {
+                self.entries[token] = Some(value);
+                token
+            }
+            None => {
+                self.entries.push(Some(value));
+                self.entries.len() - 1
+            }
+        };
+    }
+
+    pub fn get(&self, token: usize) -> Option<&T> {
+        return self.entries.get(token).and_then(Option::as_ref);
+    }
+
+    #[cfg(feature = "io-uring")]
+    pub fn get_mut(&mut self, token: usize) -> Option<&mut T> {
+        return self.entries.get_mut(token).and_then(Option::as_mut);
+    }
+
+    pub fn remove(&mut self, token: usize) -> Option<T> {
+        let value = self.entries.get_mut(token)?.take();
+        if value.is_some() {
+            self.free.push(token);
+        }
+        return value;
+    }
+
+    /// iterates over all occupied slots
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        return self.entries.iter().filter_map(Option::as_ref);
+    }
+
+    #[cfg(feature = "io-uring")]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        return self.entries.iter_mut().filter_map(Option::as_mut);
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        return self.entries.len() - self.free.len();
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Slab;
+
+    #[test]
+    fn reuse_tokens() {
+        let mut slab = Slab::new();
+        let a = slab.insert("a");
+        let b = slab.insert("b");
+        assert_ne!(a, b);
+        assert_eq!(slab.len(), 2);
+
+        assert_eq!(slab.remove(a), Some("a"));
+        assert_eq!(slab.remove(a), None);
+        assert_eq!(slab.get(a), None);
+
+        let c = slab.insert("c");
+        assert_eq!(c, a);
+        assert_eq!(slab.get(c), Some(&"c"));
+        assert_eq!(slab.get(b), Some(&"b"));
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    any::Any,
+    collections::VecDeque,
+    future::Future,
+    io,
+    os::fd::{AsRawFd, RawFd},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use io_uring::{opcode, squeue, types, IoUring};
+
+use super::{
+    epoll::Epoll,
+    interest::{Event, Interest},
+    reactor::{gone, Reactor},
+    slab::Slab,
+};
+
+/// the token the eventfd signaling completions is registered with in the epoll queue
+const URING_TOKEN: usize = usize::MAX - 1;
+/// the user data of cancelations, their completions are ignored
+const CANCEL_USER_DATA: u64 = u64::MAX;
+const ENTRIES: u32 = 256;
+
+/// The state of a submitted operation
+enum Lifecycle {
+    /// the kernel is still working on it, the task waiting for it is stored once it got polled
+    Submitted(Option<Waker>),
+    /// the kernel is done, holds the result of the operation
+    Completed(i32),
+    /// the future waiting for the operation got dropped, the buffers the kernel still writes
+    /// into or reads from are kept alive until the completion arrives
+    Ignored(#[allow(dead_code)] Box<dyn Any + Send>),
+}
+
+/// A completion-based driver on top of io_uring.
+///
+/// Readiness based I/O still goes through a epoll queue, operations submitted through [Op] are
+/// completed by the kernel instead. A eventfd registered with the ring is part of the epoll queue,
+/// so a single wait is enough to learn about both
+pub struct Uring {
+    ring: Mutex<IoUring>,
+    /// entries that didn't fit into the submission queue, they are pushed once it has room again
+    backlog: Mutex<VecDeque<squeue::Entry>>,
+    ops: Mutex<Slab<Lifecycle>>,
+    epoll: Epoll,
+    event_fd: RawFd,
+}
+
+impl Default for Uring {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Uring {
+    pub fn new() -> Self {
+        let ring = IoUring::new(ENTRIES).expect("can't create a io_uring instance");
+        let event_fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if event_fd < 0 {
+            panic!("can't create a eventfd: {}", io::Error::last_os_error());
+        }
+        ring.submitter()
+            .register_eventfd(event_fd)
+            .expect("can't register the eventfd with the io_uring instance");
+
+        let epoll = Epoll::new();
+        epoll
+            .add_interest(event_fd, Event::new(URING_TOKEN, Interest::Read).into())
+            .expect("failed to add the eventfd to the epoll queue");
+
+        return Self {
+            ring: Mutex::new(ring),
+            backlog: Mutex::new(VecDeque::new()),
+            ops: Mutex::new(Slab::new()),
+            epoll,
+            event_fd,
+        };
+    }
+
+    pub fn add_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
+        return self.epoll.add_interest(fd, event.into());
+    }
+
+    pub fn modify_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
+        return self.epoll.modify_interest(fd, event.into());
+    }
+
+    pub fn remove_interest(&self, fd: RawFd, event: Event) -> io::Result<()> {
+        return self.epoll.remove_interest(fd, event.into());
+    }
+
+    /// waits for readiness events and completions. Completions wake their tasks right away,
+    /// only readiness events are written into `events`
+    pub fn wait(&self, timeout: Option<u32>, events: &mut [epoll::Event]) -> io::Result<usize> {
+        let size = self.epoll.wait(timeout, events)?;
+
+        let mut n = 0;
+        for i in 0..size {
+            if events[i].data == URING_TOKEN as u64 {
+                self.complete();
+                continue;
+            }
+            events[n] = events[i];
+            n += 1;
+        }
+        return Ok(n);
+    }
+
+    /// submits `entry` and returns the index of the operation
+    ///
+    /// # Safety
+    /// every buffer `entry` points to has to stay valid until the operation completed
+    unsafe fn submit(&self, entry: squeue::Entry) -> usize {
+        let index = self.ops.lock().unwrap().insert(Lifecycle::Submitted(None));
+        self.push(entry.user_data(index as u64));
+        return index;
+    }
+
+    /// pushes `entry` onto the submission queue and submits it. If the kernel didn't take the
+    /// queued entries off the queue, the entry waits in the backlog for the next completions
+    unsafe fn push(&self, entry: squeue::Entry) {
+        let mut ring = self.ring.lock().unwrap();
+        let mut backlog = self.backlog.lock().unwrap();
+        backlog.push_back(entry);
+        Self::flush(&mut ring, &mut backlog);
+    }
+
+    /// moves as much of the backlog onto the submission queue as fits and submits it. Failing
+    /// submits leave the entries queued, the kernel only refuses them while completions are
+    /// waiting to be reaped, so the next call of [Uring::complete] tries again
+    fn flush(ring: &mut IoUring, backlog: &mut VecDeque<squeue::Entry>) {
+        loop {
+            while let Some(entry) = backlog.front() {
+                if unsafe { ring.submission().push(entry) }.is_err() {
+                    break;
+                }
+                backlog.pop_front();
+            }
+            if backlog.is_empty() {
+                let _ = ring.submit();
+                return;
+            }
+            // the queue is full, submitting makes room unless the kernel took nothing
+            if !matches!(ring.submit(), Ok(n) if n > 0) {
+                return;
+            }
+        }
+    }
+
+    /// takes all completions off the completion queue and wakes the tasks waiting for them
+    fn complete(&self) {
+        let mut value: u64 = 0;
+        unsafe {
+            libc::read(
+                self.event_fd,
+                &mut value as *mut u64 as *mut libc::c_void,
+                core::mem::size_of::<u64>(),
+            )
+        };
+
+        let completions: Vec<_> = {
+            let mut ring = self.ring.lock().unwrap();
+            let completions = ring
+                .completion()
+                .map(|cqe| (cqe.user_data(), cqe.result()))
+                .collect();
+            Self::flush(&mut ring, &mut self.backlog.lock().unwrap());
+            completions
+        };
+
+        let mut wakers = Vec::new();
+        {
+            let mut ops = self.ops.lock().unwrap();
+            for (user_data, result) in completions {
+                if user_data == CANCEL_USER_DATA {
+                    continue;
+                }
+
+                let index = user_data as usize;
+                let Some(op) = ops.get_mut(index) else {
+                    continue;
+                };
+                match op {
+                    Lifecycle::Submitted(waker) => {
+                        wakers.extend(waker.take());
+                        *op = Lifecycle::Completed(result);
+                    }
+                    // dropping it frees the buffers of the dropped future
+                    Lifecycle::Ignored(_) => {
+                        ops.remove(index);
+                    }
+                    Lifecycle::Completed(_) => (),
+                }
+            }
+        }
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn poll_complete(&self, index: usize, cx: &mut Context<'_>) -> Poll<i32> {
+        let mut ops = self.ops.lock().unwrap();
+        return match ops.get_mut(index) {
+            Some(Lifecycle::Completed(result)) => {
+                let result = *result;
+                ops.remove(index);
+                Poll::Ready(result)
+            }
+            Some(Lifecycle::Submitted(waker)) => {
+                *waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            _ => unreachable!("a operation is only removed once it completed"),
+        };
+    }
+
+    /// forgets about the operation at `index`. If it is still running `data` is kept alive until
+    /// it completed and the kernel is asked to cancel it
+    fn cancel(&self, index: usize, data: Box<dyn Any + Send>) {
+        {
+            let mut ops = self.ops.lock().unwrap();
+            match ops.get_mut(index) {
+                Some(op @ Lifecycle::Submitted(_)) => *op = Lifecycle::Ignored(data),
+                _ => {
+                    ops.remove(index);
+                    return;
+                }
+            }
+        }
+
+        let entry = opcode::AsyncCancel::new(index as u64)
+            .build()
+            .user_data(CANCEL_USER_DATA);
+        unsafe { self.push(entry) };
+    }
+
+    /// wakes every task waiting for a operation, called once the reactor shut down
+    pub(crate) fn wake_all(&self) {
+        let mut wakers = Vec::new();
+        for op in self.ops.lock().unwrap().iter_mut() {
+            if let Lifecycle::Submitted(waker) = op {
+                wakers.extend(waker.take());
+            }
+        }
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for Uring {
+    fn drop(&mut self) {
+        // the kernel might still use the buffers of ignored operations, leaking them is the only
+        // safe thing left to do
+        let ops = core::mem::take(self.ops.get_mut().unwrap());
+        core::mem::forget(ops);
+        unsafe { libc::close(self.event_fd) };
+    }
+}
+
+/// A operation submitted to the io_uring of a [Reactor], resolving to its result together with
+/// the buffers it owned while the kernel worked on it. If the reactor shut down before the
+/// operation completed the buffers stay with the driver and the operation fails
+pub(crate) struct Op<T: Send + 'static> {
+    reactor: Arc<Reactor>,
+    index: usize,
+    data: Option<T>,
+}
+
+impl<T: Send + 'static> Op<T> {
+    /// # Safety
+    /// `entry` may only point into heap buffers owned by `data`
+    unsafe fn submit(reactor: &Arc<Reactor>, data: T, entry: squeue::Entry) -> io::Result<Self> {
+        if reactor.is_shutdown() {
+            return Err(gone());
+        }
+        let uring = reactor.uring().expect("the reactor has no io_uring driver");
+        let index = uring.submit(entry);
+        return Ok(Self {
+            reactor: reactor.clone(),
+            index,
+            data: Some(data),
+        });
+    }
+}
+
+impl<T: Send + Unpin + 'static> Future for Op<T> {
+    type Output = io::Result<(usize, T)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let uring = this
+            .reactor
+            .uring()
+            .expect("the reactor has no io_uring driver");
+        // no turn reaps completions anymore, take a look at the queue right here
+        let shutdown = this.reactor.is_shutdown();
+        if shutdown {
+            uring.complete();
+        }
+
+        let result = match uring.poll_complete(this.index, cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending if shutdown => {
+                // fail like the readiness based I/O does, the driver keeps the buffers
+                let data = this.data.take().expect("polled after completion");
+                uring.cancel(this.index, Box::new(data));
+                return Poll::Ready(Err(gone()));
+            }
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let data = this.data.take().expect("polled after completion");
+        if result < 0 {
+            return Poll::Ready(Err(io::Error::from_raw_os_error(-result)));
+        }
+        return Poll::Ready(Ok((result as usize, data)));
+    }
+}
+
+impl<T: Send + 'static> Drop for Op<T> {
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            if let Some(uring) = self.reactor.uring() {
+                uring.cancel(self.index, Box::new(data));
+            }
+        }
+    }
+}
+
+/// the length of a single operation is a u32, longer buffers are read or written partially
+fn max_len(len: usize) -> u32 {
+    return len.min(u32::MAX as usize) as u32;
+}
+
+/// receives into a owned buffer of `len` bytes
+pub(crate) fn recv(
+    reactor: &Arc<Reactor>,
+    fd: &impl AsRawFd,
+    len: usize,
+) -> io::Result<Op<Vec<u8>>> {
+    let mut buf = vec![0; max_len(len) as usize];
+    let entry = opcode::Recv::new(
+        types::Fd(fd.as_raw_fd()),
+        buf.as_mut_ptr(),
+        buf.len() as u32,
+    )
+    .build();
+    return unsafe { Op::submit(reactor, buf, entry) };
+}
+
+/// sends the owned buffer `buf`
+pub(crate) fn send(
+    reactor: &Arc<Reactor>,
+    fd: &impl AsRawFd,
+    buf: Vec<u8>,
+) -> io::Result<Op<Vec<u8>>> {
+    let entry =
+        opcode::Send::new(types::Fd(fd.as_raw_fd()), buf.as_ptr(), max_len(buf.len())).build();
+    return unsafe { Op::submit(reactor, buf, entry) };
+}
+
+/// reads `len` bytes at `offset` into a owned buffer
+pub(crate) fn read_at(
+    reactor: &Arc<Reactor>,
+    fd: &impl AsRawFd,
+    len: usize,
+    offset: u64,
+) -> io::Result<Op<Vec<u8>>> {
+    let mut buf = vec![0; max_len(len) as usize];
+    let entry = opcode::Read::new(
+        types::Fd(fd.as_raw_fd()),
+        buf.as_mut_ptr(),
+        buf.len() as u32,
+    )
+    .offset(offset)
+    .build();
+    return unsafe { Op::submit(reactor, buf, entry) };
+}
+
+/// writes the owned buffer `buf` at `offset`
+pub(crate) fn write_at(
+    reactor: &Arc<Reactor>,
+    fd: &impl AsRawFd,
+    buf: Vec<u8>,
+    offset: u64,
+) -> io::Result<Op<Vec<u8>>> {
+    let entry = opcode::Write::new(types::Fd(fd.as_raw_fd()), buf.as_ptr(), max_len(buf.len()))
+        .offset(offset)
+        .build();
+    return unsafe { Op::submit(reactor, buf, entry) };
+}
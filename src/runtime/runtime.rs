@@ -1,41 +1,81 @@
-use core::future::Future;
-use std::{
-    sync::Mutex,
-    thread::{self},
-};
+use core::{future::Future, time::Duration};
+use std::thread::{self};
 
 use alloc::sync::Arc;
 
 use crate::runtime::{
+    context::{EnterGuard, Handle},
     executor::executor::{self, Executor},
     handle::JoinHandle,
 };
 
-use super::reactor::reactor::Reactor;
+use super::reactor::{poller::Backend, reactor::Reactor};
+
+/// Decides on which threads a [Runtime] drives its tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// tasks are driven by a pool of work-stealing worker threads
+    MultiThread,
+    /// tasks are driven on the thread calling [Runtime::block_on], no threads are spawned
+    CurrentThread,
+}
 
 pub struct RuntimeBuilder {
     num_threads: usize,
+    flavor: Flavor,
+    backend: Backend,
 }
 
 impl RuntimeBuilder {
     pub fn new() -> Self {
         return Self {
             num_threads: 1, //std::thread::available_parallelism().unwrap().into(),
+            flavor: Flavor::MultiThread,
+            backend: Backend::default(),
+        };
+    }
+
+    /// creates a builder for a [Runtime] which polls the future passed to
+    /// [block_on](Runtime::block_on) and all spawned tasks on the calling thread
+    pub fn current_thread() -> Self {
+        return Self {
+            num_threads: 0,
+            flavor: Flavor::CurrentThread,
+            backend: Backend::default(),
         };
     }
 
+    /// sets the number of worker threads, ignored by the [Flavor::CurrentThread] flavor
     pub fn threads(mut self, num_threads: usize) -> Self {
         self.num_threads = num_threads;
         return self;
     }
 
+    /// sets the interface the reactor waits for I/O readiness with, defaults to epoll
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        return self;
+    }
+
     pub fn build(self) -> Runtime {
         return Runtime::from_builder(self);
     }
 }
 
+impl Default for RuntimeBuilder {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// The entry point for running futures.
+///
+/// Dropping it shuts the runtime down: the workers are signaled to stop, waited for and all
+/// remaining tasks are dropped. Afterwards the runtime's [Reactor] is stopped
 pub struct Runtime {
-    executor: Arc<Mutex<Executor>>,
+    handle: Handle,
+    flavor: Flavor,
+    workers: Vec<thread::JoinHandle<()>>,
 }
 
 impl Runtime {
@@ -45,36 +85,119 @@ impl Runtime {
     }
 
     pub fn from_builder(builder: RuntimeBuilder) -> Self {
-        let rt = Self {
-            executor: Arc::new(Mutex::new(Executor::new())),
+        let num_workers = match builder.flavor {
+            Flavor::MultiThread => builder.num_threads,
+            Flavor::CurrentThread => 0,
         };
 
-        let _ = Reactor::get();
+        let reactor = Arc::new(Reactor::with_backend(builder.backend));
+        let (executor, workers) = Executor::with_workers(num_workers, reactor.clone());
 
-        for _ in 0..builder.num_threads {
-            println!("spawning executor loop");
-            let _ = thread::spawn(executor::run_executor(rt.executor.clone()));
+        let mut rt = Self {
+            handle: Handle::new(Arc::new(executor), reactor),
+            flavor: builder.flavor,
+            workers: Vec::with_capacity(num_workers),
+        };
+
+        for (index, worker) in workers.into_iter().enumerate() {
+            let handle = rt.handle.clone();
+            let run = executor::run_executor(handle.executor().clone(), index, worker);
+            rt.workers.push(thread::spawn(move || {
+                let _enter = handle.enter();
+                run();
+            }));
         }
 
         return rt;
     }
 
+    pub fn flavor(&self) -> Flavor {
+        return self.flavor;
+    }
+
+    /// returns a cloneable handle to this runtime
+    pub fn handle(&self) -> &Handle {
+        return &self.handle;
+    }
+
+    /// makes this the current runtime of the calling thread until the returned guard is dropped,
+    /// so that I/O resources can be created outside of [Runtime::block_on]
+    pub fn enter(&self) -> EnterGuard {
+        return self.handle.enter();
+    }
+
+    fn executor(&self) -> &Arc<Executor> {
+        return self.handle.executor();
+    }
+
+    /// spawns a task onto the runtime. With the [Flavor::CurrentThread] flavor it only makes
+    /// progress while a thread is inside of [Runtime::block_on].
+    ///
+    /// The task may be moved between worker threads, so the future has to be [Send]. Futures
+    /// holding `!Send` state can be spawned onto a [LocalSet](crate::runtime::task::local::LocalSet)
+    /// instead
+    /// ```compile_fail
+    /// use std::rc::Rc;
+    /// use oxic::prelude::*;
+    ///
+    /// let mut rt = Runtime::new();
+    /// let rc = Rc::new(5);
+    /// rt.spawn(async move { *rc });
+    /// ```
     #[inline(always)]
     pub fn spawn<Fut, T>(&mut self, f: Fut) -> JoinHandle<T>
     where
-        Fut: Future<Output = T> + 'static,
-        T: 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
     {
-        return self.executor.lock().unwrap().spawn(f);
+        return self.executor().spawn(f);
     }
 
+    /// drives the future to completion on the calling thread, so it doesn't need to be [Send].
+    /// With the [Flavor::CurrentThread] flavor the spawned tasks are driven alongside it
     #[inline(always)]
     pub fn block_on<Fut, T>(&mut self, f: Fut) -> T
     where
-        Fut: Future<Output = T> + 'static,
-        T: 'static,
+        Fut: Future<Output = T>,
     {
-        return self.spawn(f).join();
+        let _enter = self.enter();
+        return self.executor().block_on(f);
+    }
+
+    /// shuts the runtime down and waits at most `timeout` for the workers to finish polling
+    /// their current task. Workers still busy after that are detached. All remaining tasks are
+    /// dropped, so their handles resolve to a cancelled [JoinError](crate::runtime::handle::JoinError)
+    pub fn shutdown_timeout(mut self, timeout: Duration) {
+        self.shutdown(Some(timeout));
+    }
+
+    /// shuts the runtime down without waiting for the workers, see [Runtime::shutdown_timeout]
+    pub fn shutdown_background(mut self) {
+        self.shutdown(Some(Duration::ZERO));
+    }
+
+    fn shutdown(&mut self, timeout: Option<Duration>) {
+        let executor = self.handle.executor();
+        if !executor.shutdown() {
+            return;
+        }
+
+        let finished = executor.wait_for_workers(timeout);
+        for worker in self.workers.drain(..) {
+            if finished || worker.is_finished() {
+                let _ = worker.join();
+            }
+        }
+
+        executor.drop_tasks();
+
+        self.handle.reactor().shutdown();
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        self.shutdown(None);
     }
 }
 
@@ -86,7 +209,7 @@ impl Default for Runtime {
 
 #[cfg(test)]
 mod tests {
-    use core::{assert_eq, future::Future, task::Poll};
+    use core::{future::Future, task::Poll};
 
     use crate::prelude::Runtime;
 
@@ -124,7 +247,7 @@ mod tests {
 
         let mut rt = Runtime::new();
         let b = rt.block_on(two_times());
-        assert_eq!(b, false);
+        assert!(!b);
     }
 
     #[test]
@@ -134,7 +257,132 @@ mod tests {
         }
 
         let mut rt = Runtime::new();
-        let err = std::panic::catch_unwind(move || rt.spawn(does_panic()));
-        //assert!(err.is_err());
+        let err = rt.spawn(does_panic()).join().unwrap_err();
+        assert!(err.is_panic());
+        assert!(!err.is_cancelled());
+        assert_eq!(
+            *err.into_panic().downcast::<&str>().unwrap(),
+            "panic happened"
+        );
+
+        // the worker has to survive the panic
+        let res = rt.spawn(async { 5 }).join();
+        assert_eq!(res.unwrap(), 5);
+    }
+
+    #[test]
+    fn current_thread() {
+        use std::{cell::RefCell, rc::Rc, thread};
+
+        use crate::runtime::runtime::RuntimeBuilder;
+
+        let mut rt = RuntimeBuilder::current_thread().build();
+        let caller = thread::current().id();
+
+        let handle = rt.spawn(async move { thread::current().id() });
+        let counter = Rc::new(RefCell::new(0));
+        let counter1 = counter.clone();
+        let spawned_on = rt.block_on(async move {
+            let tf = TestFuture { counter: 0 };
+            *counter1.borrow_mut() += tf.await;
+            handle.await.unwrap()
+        });
+
+        assert_eq!(spawned_on, caller);
+        assert_eq!(*counter.borrow(), 1);
+    }
+
+    #[test]
+    fn shutdown_drops_tasks() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        use crate::runtime::runtime::RuntimeBuilder;
+
+        struct SetOnDrop(Arc<AtomicBool>);
+        impl Drop for SetOnDrop {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let guard = SetOnDrop(dropped.clone());
+
+        let mut rt = RuntimeBuilder::new().threads(2).build();
+        let handle = rt.spawn(async move {
+            let _guard = guard;
+            core::future::pending::<()>().await;
+        });
+        let queued = rt.spawn(async { 5 });
+        assert_eq!(queued.join().unwrap(), 5);
+
+        drop(rt);
+        assert!(dropped.load(Ordering::SeqCst));
+        assert!(handle.join().unwrap_err().is_cancelled());
+    }
+
+    #[test]
+    fn shutdown_background() {
+        use std::time::{Duration, Instant};
+
+        use crate::runtime::runtime::RuntimeBuilder;
+
+        let mut rt = RuntimeBuilder::new().threads(1).build();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        // blocks the only worker until the runtime was shut down
+        rt.spawn(async move {
+            tx.send(()).unwrap();
+            let _ = released.recv();
+        });
+        rx.recv().unwrap();
+
+        let start = Instant::now();
+        rt.shutdown_background();
+        assert!(start.elapsed() < Duration::from_millis(200));
+        drop(release);
+    }
+
+    #[test]
+    fn shutdown_current_thread() {
+        use crate::runtime::runtime::RuntimeBuilder;
+
+        let mut rt = RuntimeBuilder::current_thread().build();
+        let handle = rt.spawn(async { 1 });
+        rt.shutdown_timeout(std::time::Duration::from_millis(10));
+        assert!(handle.join().unwrap_err().is_cancelled());
+    }
+
+    #[test]
+    fn handle_current() {
+        use alloc::sync::Arc;
+
+        use crate::runtime::{context::Handle, runtime::RuntimeBuilder};
+
+        assert!(Handle::try_current().is_none());
+
+        let mut rt1 = Runtime::new();
+        let rt2 = RuntimeBuilder::new().threads(2).build();
+        let reactor1 = rt1.handle().reactor().clone();
+        let reactor2 = rt2.handle().reactor().clone();
+        assert!(!Arc::ptr_eq(&reactor1, &reactor2));
+
+        let current = rt1.block_on(async { Handle::current() });
+        assert!(Arc::ptr_eq(current.reactor(), &reactor1));
+
+        let spawned = rt1
+            .spawn(async { Handle::current().reactor().clone() })
+            .join()
+            .unwrap();
+        assert!(Arc::ptr_eq(&spawned, &reactor1));
+
+        {
+            let _enter = rt2.enter();
+            assert!(Arc::ptr_eq(Handle::current().reactor(), &reactor2));
+        }
+        assert!(Handle::try_current().is_none());
     }
 }
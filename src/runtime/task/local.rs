@@ -0,0 +1,276 @@
+use alloc::{boxed::Box, collections::BTreeMap, rc::Rc, sync::Arc};
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::sync::Mutex;
+
+use crossbeam_queue::SegQueue;
+
+use crate::runtime::{
+    handle::{self, JoinHandle},
+    runtime::Runtime,
+    task::task::TaskId,
+};
+
+/// the number of local tasks run per poll of [RunUntil] before yielding back to the runtime
+const LOCAL_BUDGET: usize = 64;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Rc<LocalState>>> = const { RefCell::new(None) };
+}
+
+/// The part of a [LocalSet] that wakers, which might live on other threads, need access to
+struct LocalShared {
+    queue: SegQueue<TaskId>,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl LocalShared {
+    fn wake_task(&self, id: TaskId) {
+        self.queue.push(id);
+        if let Some(waker) = self.waker.lock().unwrap().as_ref() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+/// A waker pushing the id of a local task back onto the queue of its [LocalSet]
+struct LocalWaker {
+    id: TaskId,
+    shared: Arc<LocalShared>,
+}
+
+impl alloc::task::Wake for LocalWaker {
+    fn wake(self: Arc<Self>) {
+        self.shared.wake_task(self.id);
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.shared.wake_task(self.id);
+    }
+}
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+struct LocalState {
+    tasks: RefCell<BTreeMap<TaskId, LocalFuture>>,
+    waker_cache: RefCell<BTreeMap<TaskId, Waker>>,
+    shared: Arc<LocalShared>,
+}
+
+impl LocalState {
+    fn spawn<Fut, T>(&self, f: Fut) -> JoinHandle<T>
+    where
+        Fut: Future<Output = T> + 'static,
+        T: 'static,
+    {
+        let id = TaskId::new();
+        let (fut, handle) = handle::harness(id, f);
+        self.tasks.borrow_mut().insert(id, Box::pin(fut));
+        self.waker_cache.borrow_mut().insert(
+            id,
+            Waker::from(Arc::new(LocalWaker {
+                id,
+                shared: self.shared.clone(),
+            })),
+        );
+        self.shared.wake_task(id);
+        return handle;
+    }
+
+    fn run_task(&self, id: TaskId) {
+        // the future is taken out of the map while it is polled, so that it can spawn further
+        // local tasks
+        let Some(mut fut) = self.tasks.borrow_mut().remove(&id) else {
+            return;
+        };
+        let Some(waker) = self.waker_cache.borrow().get(&id).cloned() else {
+            return;
+        };
+
+        let mut cx = Context::from_waker(&waker);
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(()) => {
+                self.waker_cache.borrow_mut().remove(&id);
+            }
+            Poll::Pending => {
+                self.tasks.borrow_mut().insert(id, fut);
+            }
+        }
+    }
+}
+
+/// A set of `!Send` tasks which are all driven on the same thread.
+///
+/// The tasks only make progress while the set is driven through [LocalSet::run_until] or
+/// [LocalSet::block_on]
+pub struct LocalSet {
+    state: Rc<LocalState>,
+}
+
+impl LocalSet {
+    pub fn new() -> Self {
+        return Self {
+            state: Rc::new(LocalState {
+                tasks: RefCell::new(BTreeMap::new()),
+                waker_cache: RefCell::new(BTreeMap::new()),
+                shared: Arc::new(LocalShared {
+                    queue: SegQueue::new(),
+                    waker: Mutex::new(None),
+                }),
+            }),
+        };
+    }
+
+    /// spawns a future that doesn't need to be [Send] onto this set
+    pub fn spawn_local<Fut, T>(&self, f: Fut) -> JoinHandle<T>
+    where
+        Fut: Future<Output = T> + 'static,
+        T: 'static,
+    {
+        return self.state.spawn(f);
+    }
+
+    /// returns a future which drives the local tasks until `f` completes. While it is polled
+    /// [spawn_local] spawns onto this set
+    pub fn run_until<Fut>(&self, f: Fut) -> RunUntil<'_, Fut>
+    where
+        Fut: Future,
+    {
+        return RunUntil {
+            local_set: self,
+            future: Box::pin(f),
+        };
+    }
+
+    /// drives `f` and the local tasks to completion on the calling thread
+    pub fn block_on<Fut, T>(&self, rt: &mut Runtime, f: Fut) -> T
+    where
+        Fut: Future<Output = T>,
+    {
+        return rt.block_on(self.run_until(f));
+    }
+}
+
+impl Default for LocalSet {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// Restores the previously entered [LocalSet] once dropped
+struct Enter(Option<Rc<LocalState>>);
+
+impl Enter {
+    fn new(state: Rc<LocalState>) -> Self {
+        return Self(CURRENT.with(|current| current.replace(Some(state))));
+    }
+}
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// see [LocalSet::run_until]
+pub struct RunUntil<'a, Fut: Future> {
+    local_set: &'a LocalSet,
+    future: Pin<Box<Fut>>,
+}
+
+impl<Fut: Future> Future for RunUntil<'_, Fut> {
+    type Output = Fut::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = self.local_set.state.clone();
+        *state.shared.waker.lock().unwrap() = Some(cx.waker().clone());
+        let _enter = Enter::new(state.clone());
+
+        if let Poll::Ready(v) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(v);
+        }
+
+        for _ in 0..LOCAL_BUDGET {
+            match state.shared.queue.pop() {
+                Some(id) => state.run_task(id),
+                None => break,
+            }
+        }
+
+        if !state.shared.queue.is_empty() {
+            cx.waker().wake_by_ref();
+        }
+        return Poll::Pending;
+    }
+}
+
+/// spawns a `!Send` future onto the [LocalSet] that is currently being driven on this thread
+///
+/// # Panics
+/// panics when called outside of [LocalSet::run_until]
+pub fn spawn_local<Fut, T>(f: Fut) -> JoinHandle<T>
+where
+    Fut: Future<Output = T> + 'static,
+    T: 'static,
+{
+    return CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(state) => state.spawn(f),
+        None => panic!("spawn_local called outside of a LocalSet"),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use crate::prelude::Runtime;
+    use crate::test_util::YieldNow;
+
+    use super::{spawn_local, LocalSet};
+
+    #[test]
+    fn spawn_non_send() {
+        let mut rt = Runtime::new();
+        let local = LocalSet::new();
+        let value = Rc::new(RefCell::new(0));
+
+        let v = value.clone();
+        local.spawn_local(async move {
+            *v.borrow_mut() += 1;
+        });
+
+        let v = value.clone();
+        local.block_on(&mut rt, async move {
+            let v1 = v.clone();
+            spawn_local(async move {
+                *v1.borrow_mut() += 1;
+            });
+            while *v.borrow() != 2 {
+                YieldNow(1).await;
+            }
+        });
+        assert_eq!(*value.borrow(), 2);
+    }
+
+    #[test]
+    fn dropped_task_is_cancelled() {
+        let local = LocalSet::new();
+        let handle = local.spawn_local(async { 5 });
+        drop(local);
+
+        let err = handle.join().unwrap_err();
+        assert!(err.is_cancelled());
+    }
+
+    #[test]
+    #[should_panic]
+    fn spawn_local_outside_of_local_set() {
+        spawn_local(async {});
+    }
+}
@@ -1 +1,3 @@
+pub mod local;
+#[allow(clippy::module_inception)]
 pub mod task;
@@ -1,8 +1,9 @@
 use alloc::boxed::Box;
 use core::future::Future;
 use core::pin::Pin;
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
 use core::task::{Context, Poll};
+use std::sync::Mutex;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TaskId(u64);
@@ -14,22 +15,112 @@ impl TaskId {
     }
 }
 
+impl Default for TaskId {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// the task is neither queued nor being polled
+const IDLE: u8 = 0;
+/// the task sits in one of the run queues
+const SCHEDULED: u8 = 1;
+/// a worker is currently polling the task
+const RUNNING: u8 = 2;
+/// the task was woken while it was being polled and has to be queued again afterwards
+const NOTIFIED: u8 = 3;
+/// the future returned [Poll::Ready] and got dropped
+const COMPLETE: u8 = 4;
+
 /// A asynchronos path of execution, the fundamental building block of the Runtime.
 pub struct Task {
     pub(crate) id: TaskId,
-    pub(crate) future: Pin<Box<dyn Future<Output = ()>>>,
+    future: Mutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>,
+    state: AtomicU8,
 }
 
 impl Task {
-    pub fn new(fut: impl Future<Output = ()> + 'static) -> Self {
+    pub fn new(fut: impl Future<Output = ()> + Send + 'static) -> Self {
+        return Self::with_id(TaskId::new(), fut);
+    }
+
+    pub(crate) fn with_id(id: TaskId, fut: impl Future<Output = ()> + Send + 'static) -> Self {
         return Self {
-            id: TaskId::new(),
-            future: Box::pin(fut),
+            id,
+            future: Mutex::new(Some(Box::pin(fut))),
+            state: AtomicU8::new(SCHEDULED),
         };
     }
 
+    /// marks the task as scheduled. Returns true if the caller is responsible for pushing it
+    /// onto a run queue, false if it is already queued, will be requeued by the worker currently
+    /// polling it or has already completed
+    pub(crate) fn schedule(&self) -> bool {
+        let mut current = self.state.load(Ordering::Acquire);
+        loop {
+            let next = match current {
+                IDLE => SCHEDULED,
+                RUNNING => NOTIFIED,
+                _ => return false,
+            };
+
+            match self.state.compare_exchange_weak(
+                current,
+                next,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return next == SCHEDULED,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
     /// polls the asynchronos task
-    pub fn poll(&mut self, cx: &mut Context) -> Poll<()> {
-        return self.future.as_mut().poll(cx);
+    pub fn poll(&self, cx: &mut Context) -> Poll<()> {
+        self.state.store(RUNNING, Ordering::Release);
+
+        let mut future = self.future.lock().unwrap();
+        let Some(fut) = future.as_mut() else {
+            self.state.store(COMPLETE, Ordering::Release);
+            return Poll::Ready(());
+        };
+
+        match fut.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                *future = None;
+                self.state.store(COMPLETE, Ordering::Release);
+                return Poll::Ready(());
+            }
+            Poll::Pending => return Poll::Pending,
+        }
+    }
+
+    /// drops the future without driving it to completion. Does nothing if a worker is polling the
+    /// task right now, in that case it has to call this again once it is done
+    pub(crate) fn cancel(&self) {
+        let future = match self.future.try_lock() {
+            Ok(mut future) => future.take(),
+            Err(_) => return,
+        };
+
+        self.state.store(COMPLETE, Ordering::Release);
+        // dropped outside of the lock, since dropping the future might wake other tasks
+        drop(future);
+    }
+
+    /// transitions the task out of the running state after a [Task::poll] returned
+    /// [Poll::Pending]. Returns true if it was woken in the meantime and needs to be queued again
+    pub(crate) fn yielded(&self) -> bool {
+        match self
+            .state
+            .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => return false,
+            Err(_) => {
+                self.state.store(SCHEDULED, Ordering::Release);
+                return true;
+            }
+        }
     }
 }
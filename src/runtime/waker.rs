@@ -1,23 +1,39 @@
-use core::task::Waker;
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::Waker,
+};
 
-use alloc::{sync::Arc, task::Wake};
-use crossbeam_queue::SegQueue;
+use alloc::{
+    sync::{Arc, Weak},
+    task::Wake,
+};
 
-use crate::runtime::task::task::TaskId;
+use crate::runtime::{
+    executor::{executor::Executor, park::Parker},
+    task::task::Task,
+};
 
 /// A naive waker used for Signaling to the Executor that a Task can continue
 pub struct TaskWaker {
-    id: TaskId,
-    task_queue: Arc<SegQueue<TaskId>>,
+    task: Arc<Task>,
+    executor: Weak<Executor>,
 }
 
 impl TaskWaker {
-    pub fn new(id: TaskId, task_queue: Arc<SegQueue<TaskId>>) -> Waker {
-        return Waker::from(Arc::new(Self { id, task_queue }));
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(task: Arc<Task>, executor: Weak<Executor>) -> Waker {
+        return Waker::from(Arc::new(Self { task, executor }));
     }
 
-    pub(crate) fn wake_task(&self) -> () {
-        self.task_queue.push(self.id);
+    pub(crate) fn wake_task(&self) {
+        if !self.task.schedule() {
+            return;
+        }
+
+        // the executor might already be gone, in which case there is nobody left to run the task
+        if let Some(executor) = self.executor.upgrade() {
+            executor.schedule(self.task.clone());
+        }
     }
 }
 
@@ -30,3 +46,40 @@ impl Wake for TaskWaker {
         self.wake_task();
     }
 }
+
+/// The waker handed to the future driven by [Executor::block_on], it flags the future as
+/// runnable and wakes up the thread blocked on it
+pub(crate) struct BlockOnWaker {
+    notified: AtomicBool,
+    parker: Arc<Parker>,
+}
+
+impl BlockOnWaker {
+    pub(crate) fn new(parker: Arc<Parker>) -> Self {
+        // the future has to be polled at least once
+        return Self {
+            notified: AtomicBool::new(true),
+            parker,
+        };
+    }
+
+    pub(crate) fn is_notified(&self) -> bool {
+        return self.notified.load(Ordering::SeqCst);
+    }
+
+    /// clears the notification, returns whether the future has to be polled again
+    pub(crate) fn take_notified(&self) -> bool {
+        return self.notified.swap(false, Ordering::SeqCst);
+    }
+}
+
+impl Wake for BlockOnWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.notified.store(true, Ordering::SeqCst);
+        self.parker.unpark_one();
+    }
+}
@@ -0,0 +1,70 @@
+/// A unix signal that can be listened for with [signal](super::signal::signal)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SignalKind(libc::c_int);
+
+impl SignalKind {
+    /// any signal by its number, see `signal(7)`
+    pub const fn from_raw(signal: libc::c_int) -> Self {
+        return Self(signal);
+    }
+
+    pub const fn as_raw_value(&self) -> libc::c_int {
+        return self.0;
+    }
+
+    /// `SIGALRM`
+    pub const fn alarm() -> Self {
+        return Self(libc::SIGALRM);
+    }
+
+    /// `SIGCHLD`
+    pub const fn child() -> Self {
+        return Self(libc::SIGCHLD);
+    }
+
+    /// `SIGHUP`, usually sent when the controlling terminal closed or to reload a daemon
+    pub const fn hangup() -> Self {
+        return Self(libc::SIGHUP);
+    }
+
+    /// `SIGINT`, sent by ctrl-c in a terminal
+    pub const fn interrupt() -> Self {
+        return Self(libc::SIGINT);
+    }
+
+    /// `SIGPIPE`
+    pub const fn pipe() -> Self {
+        return Self(libc::SIGPIPE);
+    }
+
+    /// `SIGQUIT`
+    pub const fn quit() -> Self {
+        return Self(libc::SIGQUIT);
+    }
+
+    /// `SIGTERM`, the polite request to shut down
+    pub const fn terminate() -> Self {
+        return Self(libc::SIGTERM);
+    }
+
+    /// `SIGUSR1`
+    pub const fn user_defined1() -> Self {
+        return Self(libc::SIGUSR1);
+    }
+
+    /// `SIGUSR2`
+    pub const fn user_defined2() -> Self {
+        return Self(libc::SIGUSR2);
+    }
+
+    /// `SIGWINCH`, sent when the terminal got resized
+    pub const fn window_change() -> Self {
+        return Self(libc::SIGWINCH);
+    }
+}
+
+impl From<libc::c_int> for SignalKind {
+    fn from(signal: libc::c_int) -> Self {
+        return Self::from_raw(signal);
+    }
+}
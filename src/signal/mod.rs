@@ -0,0 +1,7 @@
+pub mod kind;
+pub(crate) mod registry;
+#[allow(clippy::module_inception)]
+pub mod signal;
+
+pub use kind::SignalKind;
+pub use signal::{ctrl_c, signal, Signal};
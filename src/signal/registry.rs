@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    io, mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::Waker,
+};
+
+use crate::net::sys;
+
+/// signals whose default action can't be changed or which are raised by faults of the thread
+const FORBIDDEN: [libc::c_int; 6] = [
+    libc::SIGKILL,
+    libc::SIGSTOP,
+    libc::SIGSEGV,
+    libc::SIGBUS,
+    libc::SIGILL,
+    libc::SIGFPE,
+];
+
+/// The deliveries of a signal to one listener
+pub(crate) struct Slot {
+    deliveries: AtomicUsize,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl Slot {
+    /// consumes one delivery if there is any
+    pub(crate) fn take_delivery(&self) -> bool {
+        return self
+            .deliveries
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .is_ok();
+    }
+
+    pub(crate) fn set_waker(&self, waker: &Waker) {
+        *self.waker.lock().unwrap() = Some(waker.clone());
+    }
+}
+
+/// The process wide signalfd together with the listeners of every signal it reports
+struct Registry {
+    fd: OwnedFd,
+    mask: libc::sigset_t,
+    listeners: HashMap<libc::c_int, Vec<Weak<Slot>>>,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+/// adds a listener for `signal`. Returns its slot and a duplicate of the signalfd, every
+/// listener registers its own duplicate with its reactor so all of them get woken up
+pub(crate) fn listen(signal: libc::c_int) -> io::Result<(Arc<Slot>, OwnedFd)> {
+    if signal < 1 || signal > libc::SIGRTMAX() || FORBIDDEN.contains(&signal) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "can't listen for this signal",
+        ));
+    }
+
+    let mut registry = REGISTRY.lock().unwrap();
+    if registry.is_none() {
+        let mut mask: libc::sigset_t = unsafe { mem::zeroed() };
+        unsafe { libc::sigemptyset(&mut mask) };
+        let fd =
+            sys::cvt(unsafe { libc::signalfd(-1, &mask, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC) })?;
+        *registry = Some(Registry {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            mask,
+            listeners: HashMap::new(),
+        });
+    }
+    let registry = registry.as_mut().unwrap();
+
+    if !registry.listeners.contains_key(&signal) {
+        block(signal)?;
+        unsafe { libc::sigaddset(&mut registry.mask, signal) };
+        sys::cvt(unsafe { libc::signalfd(registry.fd.as_raw_fd(), &registry.mask, 0) })?;
+    }
+
+    let slot = Arc::new(Slot {
+        deliveries: AtomicUsize::new(0),
+        waker: Mutex::new(None),
+    });
+    let listeners = registry.listeners.entry(signal).or_default();
+    listeners.retain(|listener| listener.strong_count() > 0);
+    listeners.push(Arc::downgrade(&slot));
+
+    let fd = sys::cvt(unsafe { libc::fcntl(registry.fd.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 0) })?;
+    return Ok((slot, unsafe { OwnedFd::from_raw_fd(fd) }));
+}
+
+/// reads every pending signal off the signalfd and hands it to the listeners, returns once the
+/// signalfd would block
+pub(crate) fn drain(fd: &impl AsRawFd) -> io::Result<()> {
+    loop {
+        let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
+        let n = unsafe {
+            libc::read(
+                fd.as_raw_fd(),
+                &mut info as *mut libc::signalfd_siginfo as *mut libc::c_void,
+                mem::size_of::<libc::signalfd_siginfo>(),
+            )
+        };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        dispatch(info.ssi_signo as libc::c_int);
+    }
+}
+
+fn dispatch(signal: libc::c_int) {
+    let mut wakers = Vec::new();
+    if let Some(registry) = REGISTRY.lock().unwrap().as_ref() {
+        let listeners = registry.listeners.get(&signal).into_iter().flatten();
+        for slot in listeners.filter_map(Weak::upgrade) {
+            slot.deliveries.fetch_add(1, Ordering::AcqRel);
+            wakers.extend(slot.waker.lock().unwrap().take());
+        }
+    }
+
+    for waker in wakers {
+        waker.wake();
+    }
+    if signal == libc::SIGCHLD {
+        crate::process::sigchld::wake_all();
+    }
+}
+
+/// blocks `signal` in the calling thread so it stays pending for the signalfd. Other threads
+/// might still have it unblocked, the handler makes them block it once it gets delivered there
+fn block(signal: libc::c_int) -> io::Result<()> {
+    let mut action: libc::sigaction = unsafe { mem::zeroed() };
+    action.sa_sigaction = on_signal as *const () as libc::sighandler_t;
+    action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+    unsafe { libc::sigemptyset(&mut action.sa_mask) };
+    sys::cvt(unsafe { libc::sigaction(signal, &action, std::ptr::null_mut()) })?;
+
+    let mut set: libc::sigset_t = unsafe { mem::zeroed() };
+    unsafe {
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, signal);
+    }
+    let res = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) };
+    if res != 0 {
+        return Err(io::Error::from_raw_os_error(res));
+    }
+    return Ok(());
+}
+
+/// runs when the signal went to a thread that doesn't block it yet, so the signalfd didn't see
+/// it. The signal stays blocked in the thread once the handler returned and gets sent to the
+/// process again, until every thread blocks it and it ends up pending for the signalfd
+extern "C" fn on_signal(signal: libc::c_int, _: *mut libc::siginfo_t, context: *mut libc::c_void) {
+    unsafe {
+        let errno = *libc::__errno_location();
+        let context = context as *mut libc::ucontext_t;
+        libc::sigaddset(&mut (*context).uc_sigmask, signal);
+        libc::kill(libc::getpid(), signal);
+        *libc::__errno_location() = errno;
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    future::Future,
+    io,
+    os::fd::OwnedFd,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use super::{
+    kind::SignalKind,
+    registry::{self, Slot},
+};
+use crate::io::{async_fd::AsyncFd, stream::Stream};
+use crate::runtime::reactor::interest::Interest;
+
+/// A stream of the deliveries of a signal, created by [signal]. Deliveries that arrive while
+/// nobody polls are counted, but the kernel might already have merged several of them into one
+pub struct Signal {
+    io: AsyncFd<OwnedFd>,
+    slot: Arc<Slot>,
+}
+
+/// listens for `kind`, every listener of a signal gets notified of each delivery
+///
+/// The signal is received through a `signalfd`, so it gets blocked in every thread and its
+/// default action (like terminating the process) doesn't happen anymore. That stays the case
+/// after the listeners are dropped
+///
+/// ```no_run
+/// use oxic::{prelude::Runtime, signal::{signal, SignalKind}};
+///
+/// let mut rt = Runtime::new();
+/// rt.block_on(async {
+///     let mut terminate = signal(SignalKind::terminate()).unwrap();
+///     terminate.recv().await;
+///     println!("shutting down");
+/// });
+/// ```
+///
+/// # Panics
+/// panics when called outside of the context of a runtime, see
+/// [Handle::current](crate::runtime::context::Handle::current)
+pub fn signal(kind: SignalKind) -> io::Result<Signal> {
+    let (slot, fd) = registry::listen(kind.as_raw_value())?;
+    return Ok(Signal {
+        io: AsyncFd::with_interest(fd, Interest::Read)?,
+        slot,
+    });
+}
+
+/// resolves once `SIGINT` arrives, which is what ctrl-c in a terminal sends
+pub async fn ctrl_c() -> io::Result<()> {
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    return match interrupt.recv().await {
+        Some(()) => Ok(()),
+        None => Err(io::Error::other("the signal stream ended")),
+    };
+}
+
+impl Signal {
+    /// waits for the next delivery, `None` once the reactor shut down
+    pub fn recv(&mut self) -> Recv<'_> {
+        return Recv { signal: self };
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        loop {
+            if self.slot.take_delivery() {
+                return Poll::Ready(Some(()));
+            }
+            // whichever listener reads the signalfd first hands the deliveries to the others
+            self.slot.set_waker(cx.waker());
+            if self.slot.take_delivery() {
+                return Poll::Ready(Some(()));
+            }
+
+            let Ok(mut guard) = ready!(self.io.poll_read_ready(cx)) else {
+                return Poll::Ready(None);
+            };
+            if let Ok(Err(_)) = guard.try_io(|fd| registry::drain(fd.get_ref())) {
+                return Poll::Ready(None);
+            }
+        }
+    }
+}
+
+pub struct Recv<'a> {
+    signal: &'a mut Signal,
+}
+
+impl Future for Recv<'_> {
+    type Output = Option<()>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        return self.signal.poll_recv(cx);
+    }
+}
+
+impl Stream for Signal {
+    type Item = ();
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        return self.get_mut().poll_recv(cx);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ctrl_c, signal, SignalKind};
+    use crate::io::stream::StreamExt;
+    use crate::prelude::Runtime;
+    use crate::test_util::Join;
+
+    fn raise(kind: SignalKind) {
+        unsafe { libc::kill(libc::getpid(), kind.as_raw_value()) };
+    }
+
+    #[test]
+    fn every_listener_gets_notified() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut a = signal(SignalKind::user_defined1()).unwrap();
+            let mut b = signal(SignalKind::user_defined1()).unwrap();
+            raise(SignalKind::user_defined1());
+            assert_eq!(a.recv().await, Some(()));
+            assert_eq!(b.recv().await, Some(()));
+        });
+    }
+
+    #[test]
+    fn stream_of_deliveries() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let mut deliveries = signal(SignalKind::user_defined2()).unwrap();
+            for _ in 0..3 {
+                raise(SignalKind::user_defined2());
+                assert_eq!(deliveries.next().await, Some(()));
+            }
+        });
+    }
+
+    #[test]
+    fn ctrl_c_resolves() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            // ctrl_c listens before the signal is raised, any drain of the signalfd in between
+            // would hand the delivery only to the listeners that existed at that point
+            let raise = async { raise(SignalKind::interrupt()) };
+            let (res, ()) = Join::new(ctrl_c(), raise).await;
+            res.unwrap();
+        });
+    }
+
+    #[test]
+    fn invalid_signal() {
+        let mut rt = Runtime::new();
+        rt.block_on(async {
+            let err = signal(SignalKind::from_raw(libc::SIGKILL)).err().unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        });
+    }
+}
@@ -0,0 +1,71 @@
+//! Helpers shared by the unit tests of the crate
+
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// yields back to the scheduler the given number of times before completing
+pub(crate) struct YieldNow(pub(crate) u32);
+
+impl Future for YieldNow {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 == 0 {
+            return Poll::Ready(());
+        }
+        self.0 -= 1;
+        cx.waker().wake_by_ref();
+        return Poll::Pending;
+    }
+}
+
+/// polls two futures concurrently until both completed
+pub(crate) struct Join<A: Future, B: Future> {
+    a: Pin<Box<A>>,
+    a_out: Option<A::Output>,
+    b: Pin<Box<B>>,
+    b_out: Option<B::Output>,
+}
+
+impl<A: Future, B: Future> Join<A, B> {
+    pub(crate) fn new(a: A, b: B) -> Self {
+        return Self {
+            a: Box::pin(a),
+            a_out: None,
+            b: Box::pin(b),
+            b_out: None,
+        };
+    }
+}
+
+impl<A: Future, B: Future> Future for Join<A, B>
+where
+    A::Output: Unpin,
+    B::Output: Unpin,
+{
+    type Output = (A::Output, B::Output);
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.a_out.is_none() {
+            if let Poll::Ready(v) = self.a.as_mut().poll(cx) {
+                self.a_out = Some(v);
+            }
+        }
+        if self.b_out.is_none() {
+            if let Poll::Ready(v) = self.b.as_mut().poll(cx) {
+                self.b_out = Some(v);
+            }
+        }
+        if self.a_out.is_some() && self.b_out.is_some() {
+            return Poll::Ready((self.a_out.take().unwrap(), self.b_out.take().unwrap()));
+        }
+        return Poll::Pending;
+    }
+}
+
+/// a path in the temporary directory, unique per test process
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!("oxic-{}-{}", name, std::process::id()));
+}